@@ -0,0 +1,147 @@
+use algorithms::chained::ChainedHashTable;
+use algorithms::cuckoo::CuckooHashTable;
+use algorithms::flat::FlatHashTable;
+use algorithms::hashtable::{HashTable, ProbeStrategy};
+use algorithms::robin_hood::RobinHoodHashTable;
+use criterion::{criterion_group, criterion_main, Bencher, BenchmarkId, Criterion};
+use rand::Rng;
+
+fn random_keys(length: usize) -> Vec<u64> {
+    let mut rng = rand::thread_rng();
+    (0..length).map(|_| rng.gen()).collect()
+}
+
+// The tables share their method names but no trait, so every workload is
+// written once per table by this macro.
+macro_rules! workloads {
+    ($name:ident, $new:expr) => {
+        mod $name {
+            use super::*;
+
+            // insert everything into a table that has to grow on the way
+            pub fn insert(b: &mut Bencher, keys: &[u64]) {
+                b.iter(|| {
+                    let mut m = $new;
+                    for &key in keys {
+                        m.insert(key, key);
+                    }
+                });
+            }
+
+            // look up every key present, then as many absent ones, in a
+            // table filled beforehand
+            pub fn lookup(b: &mut Bencher, keys: &[u64]) {
+                let (present, absent) = keys.split_at(keys.len() / 2);
+                let mut m = $new;
+                for &key in present {
+                    m.insert(key, key);
+                }
+                b.iter(|| {
+                    for key in present.iter().chain(absent) {
+                        criterion::black_box(m.get(key));
+                    }
+                });
+            }
+
+            // keep the table at the same size while replacing its items
+            pub fn churn(b: &mut Bencher, keys: &[u64]) {
+                let (initial, rest) = keys.split_at(keys.len() / 4);
+                b.iter(|| {
+                    let mut m = $new;
+                    for &key in initial {
+                        m.insert(key, key);
+                    }
+                    for (old, &new) in keys.iter().zip(rest) {
+                        m.remove(old);
+                        m.insert(new, new);
+                    }
+                });
+            }
+        }
+    };
+}
+
+workloads!(linear, HashTable::default());
+workloads!(
+    quadratic,
+    HashTable::with_probe_strategy(ProbeStrategy::Quadratic)
+);
+workloads!(
+    triangular,
+    HashTable::with_probe_strategy(ProbeStrategy::Triangular)
+);
+workloads!(
+    double_hashing,
+    HashTable::with_probe_strategy(ProbeStrategy::DoubleHashing)
+);
+workloads!(robin_hood, RobinHoodHashTable::default());
+workloads!(chained, ChainedHashTable::default());
+workloads!(cuckoo, CuckooHashTable::default());
+workloads!(flat, FlatHashTable::default());
+
+// a workload for each table, by name; each one times only its own part
+type Workloads<'a> = [(&'a str, fn(&mut Bencher, &[u64]))];
+
+fn bench_tables(c: &mut Criterion, group_name: &str, tables: &Workloads<'_>) {
+    let mut group = c.benchmark_group(group_name);
+    for n in (2..6).map(|i| 10_usize.pow(i)) {
+        let keys = random_keys(n);
+        for (name, f) in tables {
+            group.bench_with_input(BenchmarkId::new(*name, n), &keys, |b, k| f(b, k));
+        }
+    }
+}
+
+fn insert_benchmark(c: &mut Criterion) {
+    bench_tables(
+        c,
+        "hashtable insert",
+        &[
+            ("linear", linear::insert),
+            ("quadratic", quadratic::insert),
+            ("triangular", triangular::insert),
+            ("double hashing", double_hashing::insert),
+            ("robin hood", robin_hood::insert),
+            ("chained", chained::insert),
+            ("cuckoo", cuckoo::insert),
+            ("flat", flat::insert),
+        ],
+    );
+}
+
+fn lookup_benchmark(c: &mut Criterion) {
+    bench_tables(
+        c,
+        "hashtable lookup",
+        &[
+            ("linear", linear::lookup),
+            ("quadratic", quadratic::lookup),
+            ("triangular", triangular::lookup),
+            ("double hashing", double_hashing::lookup),
+            ("robin hood", robin_hood::lookup),
+            ("chained", chained::lookup),
+            ("cuckoo", cuckoo::lookup),
+            ("flat", flat::lookup),
+        ],
+    );
+}
+
+fn churn_benchmark(c: &mut Criterion) {
+    bench_tables(
+        c,
+        "hashtable churn",
+        &[
+            ("linear", linear::churn),
+            ("quadratic", quadratic::churn),
+            ("triangular", triangular::churn),
+            ("double hashing", double_hashing::churn),
+            ("robin hood", robin_hood::churn),
+            ("chained", chained::churn),
+            ("cuckoo", cuckoo::churn),
+            ("flat", flat::churn),
+        ],
+    );
+}
+
+criterion_group!(benches, insert_benchmark, lookup_benchmark, churn_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+use algorithms::heap::DaryHeap;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+
+fn random_array(length: usize) -> Vec<u64> {
+    let mut rng = rand::thread_rng();
+    (0..length).map(|_| rng.gen()).collect()
+}
+
+// push everything, then pop everything
+fn push_pop<const D: usize>(values: &[u64]) {
+    let mut heap = DaryHeap::<u64, _, D>::with_capacity(values.len(), |a, b| a.cmp(b));
+    for &value in values {
+        heap.push(value);
+    }
+    while heap.pop().is_some() {}
+}
+
+// Dijkstra-like workload: every pop is followed by a few pushes of larger keys
+fn decrease_heavy<const D: usize>(values: &[u64]) {
+    let mut heap = DaryHeap::<u64, _, D>::new(|a, b| a.cmp(b));
+    heap.push(0);
+    let mut iter = values.iter();
+    while let Some(top) = heap.pop() {
+        for &value in iter.by_ref().take(4) {
+            heap.push(top + value % 1024);
+        }
+    }
+}
+
+fn bench_heap_func(c: &mut Criterion, group_name: &str, f: fn(&[u64]), arity: usize) {
+    let mut group = c.benchmark_group(group_name);
+    for n in (2..6).map(|i| 10_usize.pow(i)) {
+        let values = random_array(n);
+        group.bench_with_input(
+            BenchmarkId::new(format!("{}-ary", arity), n),
+            &values,
+            |b, v| {
+                b.iter(|| f(v));
+            },
+        );
+    }
+}
+
+fn push_pop_benchmark(c: &mut Criterion) {
+    bench_heap_func(c, "heap push/pop", push_pop::<2>, 2);
+    bench_heap_func(c, "heap push/pop", push_pop::<4>, 4);
+    bench_heap_func(c, "heap push/pop", push_pop::<8>, 8);
+}
+
+fn decrease_heavy_benchmark(c: &mut Criterion) {
+    bench_heap_func(c, "heap dijkstra-like", decrease_heavy::<2>, 2);
+    bench_heap_func(c, "heap dijkstra-like", decrease_heavy::<4>, 4);
+    bench_heap_func(c, "heap dijkstra-like", decrease_heavy::<8>, 8);
+}
+
+criterion_group!(benches, push_pop_benchmark, decrease_heavy_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,749 @@
+use crate::hashtable::{DefaultState, INITIAL_BASE_SIZE};
+use crate::prime::next_prime;
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Index;
+use std::slice;
+use std::vec;
+
+/// Hash table with separate chaining.
+///
+/// Every bucket holds a linked list of the items that hash to it, so the table
+/// keeps working well above one item per bucket, and growing it relinks the
+/// nodes instead of moving keys and values around. It grows once there are
+/// two items per bucket on average. The API is the same as `HashTable`'s.
+pub struct ChainedHashTable<K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    buckets: Vec<Link<K, V>>,
+    items: usize,
+    hash_builder: S,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    next: Link<K, V>,
+}
+
+// number of buckets to hold `items` items without going above 200% load
+fn buckets_for(items: usize) -> usize {
+    (items / 2 + 1).max(INITIAL_BASE_SIZE)
+}
+
+impl<K: Eq + Hash, V> Default for ChainedHashTable<K, V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<K: Eq + Hash, V> ChainedHashTable<K, V> {
+    // room for `capacity` items before the table has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultState::default())
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ChainedHashTable<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut m = Self {
+            buckets: Vec::new(),
+            items: 0,
+            hash_builder,
+        };
+        m.resize(buckets_for(capacity));
+        m
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        // if load is about to go above 2, resize
+        if self.items >= self.capacity() {
+            self.resize(self.bucket_count() * 2);
+        }
+        let bucket = self.find_index(&key);
+        match self.position(bucket, &key) {
+            Some(pos) => Entry::Occupied(OccupiedEntry {
+                table: self,
+                bucket,
+                pos,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                table: self,
+                key,
+                bucket,
+            }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let bucket = self.find_index(key);
+        let mut link = self.buckets[bucket].as_deref_mut();
+        while let Some(node) = link {
+            if node.key.borrow() == key {
+                return Some(&mut node.value);
+            }
+            link = node.next.as_deref_mut();
+        }
+        None
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let bucket = self.find_index(key);
+        chain(&self.buckets[bucket])
+            .find(|node| node.key.borrow() == key)
+            .map(|node| (&node.key, &node.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let bucket = self.find_index(key);
+        let pos = self.position(bucket, key)?;
+        let removed = self.unlink(bucket, pos);
+
+        // if load is below 0.1, resize
+        if self.load() < 10 {
+            self.resize(self.bucket_count() / 2);
+        }
+        Some(removed)
+    }
+
+    // keeps only the items for which `f` returns true
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        self.unlink_where(|key, value| !f(key, value), |_, _| {});
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    // removes all items but keeps the buckets
+    pub fn clear(&mut self) -> Vec<(K, V)> {
+        self.drain().collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.buckets.iter(),
+            node: None,
+            remaining: self.items,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            buckets: self.buckets.iter_mut(),
+            node: None,
+            remaining: self.items,
+        }
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl ExactSizeIterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    // removes all items right away, the iterator hands them out, and keeps the buckets
+    pub fn drain(&mut self) -> IntoIter<K, V> {
+        let buckets = (0..self.buckets.len()).map(|_| None).collect();
+        IntoIter {
+            buckets: mem::replace(&mut self.buckets, buckets).into_iter(),
+            node: None,
+            remaining: mem::take(&mut self.items),
+        }
+    }
+
+    // removes the items for which `pred` returns true as the iterator reaches
+    // them, the items it does not get to stay in the table
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, K, V, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let unvisited = self.items;
+        DrainFilter {
+            buckets: self.buckets.iter_mut(),
+            link: None,
+            items: &mut self.items,
+            unvisited,
+            pred,
+        }
+    }
+
+    // number of items the table holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    // number of buckets, the table grows once it holds twice as many items
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // makes room for `additional` more items without growing on the way
+    pub fn reserve(&mut self, additional: usize) {
+        if self.items + additional > self.capacity() {
+            self.resize(buckets_for(self.items + additional));
+        }
+    }
+
+    // shrinks the buckets as much as the load factor allows
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(buckets_for(self.items));
+    }
+
+    // the number of items in the longest chain
+    pub fn max_chain_len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|link| chain(link).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn position<Q>(&self, bucket: usize, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        chain(&self.buckets[bucket]).position(|node| node.key.borrow() == key)
+    }
+
+    fn node_mut(&mut self, bucket: usize, pos: usize) -> &mut Node<K, V> {
+        let mut node = self.buckets[bucket].as_deref_mut().unwrap();
+        for _ in 0..pos {
+            node = node.next.as_deref_mut().unwrap();
+        }
+        node
+    }
+
+    fn unlink(&mut self, bucket: usize, pos: usize) -> (K, V) {
+        let mut link = &mut self.buckets[bucket];
+        for _ in 0..pos {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        let node = link.take().unwrap();
+        *link = node.next;
+        self.items -= 1;
+        (node.key, node.value)
+    }
+
+    // unlinks every item for which `pred` returns true and hands it to `removed`
+    fn unlink_where<P, R>(&mut self, mut pred: P, mut removed: R)
+    where
+        P: FnMut(&K, &mut V) -> bool,
+        R: FnMut(K, V),
+    {
+        for bucket in 0..self.buckets.len() {
+            let mut link = &mut self.buckets[bucket];
+            while link.is_some() {
+                let node = link.as_mut().unwrap();
+                if pred(&node.key, &mut node.value) {
+                    let node = link.take().unwrap();
+                    *link = node.next;
+                    self.items -= 1;
+                    removed(node.key, node.value);
+                } else {
+                    link = &mut link.as_mut().unwrap().next;
+                }
+            }
+        }
+    }
+
+    fn find_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    // moves the nodes over to the new buckets, the items themselves stay where they are
+    fn resize(&mut self, size: usize) {
+        if size < INITIAL_BASE_SIZE {
+            return;
+        }
+        let size = next_prime(size);
+        let old = mem::replace(&mut self.buckets, (0..size).map(|_| None).collect());
+        for mut link in old {
+            while let Some(mut node) = link {
+                link = node.next.take();
+                let bucket = self.find_index(&node.key);
+                node.next = self.buckets[bucket].take();
+                self.buckets[bucket] = Some(node);
+            }
+        }
+    }
+
+    fn load(&self) -> usize {
+        self.items * 100 / self.bucket_count()
+    }
+}
+
+fn chain<K, V>(link: &Link<K, V>) -> impl Iterator<Item = &Node<K, V>> {
+    let mut link = link.as_deref();
+    std::iter::from_fn(move || {
+        let node = link?;
+        link = node.next.as_deref();
+        Some(node)
+    })
+}
+
+// unlinks the nodes one by one, dropping a long chain recursively could overflow the stack
+impl<K: Eq + Hash, V, S: BuildHasher> Drop for ChainedHashTable<K, V, S> {
+    fn drop(&mut self) {
+        for bucket in &mut self.buckets {
+            let mut link = bucket.take();
+            while let Some(mut node) = link {
+                link = node.next.take();
+            }
+        }
+    }
+}
+
+impl<K, V, S> Debug for ChainedHashTable<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> Clone for ChainedHashTable<K, V, S>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    fn clone(&self) -> Self {
+        let buckets = self
+            .buckets
+            .iter()
+            .map(|link| {
+                // rebuild the chain back to front, keeping its order
+                let nodes: Vec<_> = chain(link).collect();
+                nodes.into_iter().rev().fold(None, |next, node| {
+                    Some(Box::new(Node {
+                        key: node.key.clone(),
+                        value: node.value.clone(),
+                        next,
+                    }))
+                })
+            })
+            .collect();
+        Self {
+            buckets,
+            items: self.items,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K, V, S> PartialEq for ChainedHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        self.iter()
+            .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+// panics if the key is not in the table
+impl<K, Q, V, S> Index<&Q> for ChainedHashTable<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for ChainedHashTable<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for ChainedHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::with_hasher(S::default());
+        m.extend(iter);
+        m
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> IntoIterator for ChainedHashTable<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> IntoIter<K, V> {
+        self.drain()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a ChainedHashTable<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a mut ChainedHashTable<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// The iterators walk the chains bucket by bucket and count down the items
+// still ahead, which makes `size_hint` exact.
+
+pub struct Iter<'a, K, V> {
+    buckets: slice::Iter<'a, Link<K, V>>,
+    node: Option<&'a Node<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while self.node.is_none() {
+            self.node = self.buckets.next()?.as_deref();
+        }
+        let node = self.node.take().unwrap();
+        self.node = node.next.as_deref();
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    buckets: slice::IterMut<'a, Link<K, V>>,
+    node: Option<&'a mut Node<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while self.node.is_none() {
+            self.node = self.buckets.next()?.as_deref_mut();
+        }
+        let Node { key, value, next } = self.node.take().unwrap();
+        self.node = next.as_deref_mut();
+        self.remaining -= 1;
+        Some((&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    buckets: vec::IntoIter<Link<K, V>>,
+    node: Link<K, V>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.node.is_none() {
+            self.node = self.buckets.next()?;
+        }
+        let node = self.node.take().unwrap();
+        self.node = node.next;
+        self.remaining -= 1;
+        Some((node.key, node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+// `link` is the cursor into the chain being filtered, it points at the link
+// holding the next node to visit.
+pub struct DrainFilter<'a, K, V, F> {
+    buckets: slice::IterMut<'a, Link<K, V>>,
+    link: Option<&'a mut Link<K, V>>,
+    items: &'a mut usize,
+    unvisited: usize,
+    pred: F,
+}
+
+impl<K, V, F> Iterator for DrainFilter<'_, K, V, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.unvisited > 0 {
+            let link = match self.link.take() {
+                Some(link) if link.is_some() => link,
+                _ => {
+                    self.link = Some(self.buckets.next()?);
+                    continue;
+                }
+            };
+            self.unvisited -= 1;
+            let node = link.as_mut().unwrap();
+            if (self.pred)(&node.key, &mut node.value) {
+                let node = link.take().unwrap();
+                *link = node.next;
+                self.link = Some(link);
+                *self.items -= 1;
+                return Some((node.key, node.value));
+            }
+            self.link = Some(&mut link.as_mut().unwrap().next);
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.unvisited))
+    }
+}
+
+/// A view into a single item of a `ChainedHashTable`, returned by `ChainedHashTable::entry`.
+pub enum Entry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut ChainedHashTable<K, V, S>,
+    bucket: usize,
+    // position in the chain
+    pos: usize,
+}
+
+pub struct VacantEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut ChainedHashTable<K, V, S>,
+    key: K,
+    bucket: usize,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(&entry.key);
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.node().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.table.node_mut(self.bucket, self.pos).value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.table.node_mut(self.bucket, self.pos).value
+    }
+
+    // returns the old value, the key is left as it is
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.table.unlink(self.bucket, self.pos)
+    }
+
+    fn node(&self) -> &Node<K, V> {
+        chain(&self.table.buckets[self.bucket])
+            .nth(self.pos)
+            .unwrap()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // the new item goes to the front of its chain
+    pub fn insert(self, value: V) -> &'a mut V {
+        let link = &mut self.table.buckets[self.bucket];
+        let next = link.take();
+        let node = link.insert(Box::new(Node {
+            key: self.key,
+            value,
+            next,
+        }));
+        self.table.items += 1;
+        &mut node.value
+    }
+}
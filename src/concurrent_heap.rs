@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::heap::BinaryHeap;
+
+/// Priority queue that can be shared between threads, e.g. through an `Arc`.
+///
+/// It is a `heap::BinaryHeap` behind a mutex, with a condition variable so that
+/// consumers can sleep until an item is pushed. Items are ordered by `cmp` the
+/// same way as in `heap::BinaryHeap`.
+pub struct ConcurrentHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    state: Mutex<State<T, F>>,
+    pushed: Condvar,
+}
+
+struct State<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    heap: BinaryHeap<T, F>,
+    closed: bool,
+}
+
+impl<T: Ord> ConcurrentHeap<T, fn(&T, &T) -> Ordering> {
+    pub fn min() -> Self {
+        Self::from_heap(BinaryHeap::min())
+    }
+
+    pub fn max() -> Self {
+        Self::from_heap(BinaryHeap::max())
+    }
+}
+
+impl<T, F> ConcurrentHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    pub fn new(cmp: F) -> Self {
+        Self::from_heap(BinaryHeap::new(cmp))
+    }
+
+    pub fn from_heap(heap: BinaryHeap<T, F>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                heap,
+                closed: false,
+            }),
+            pushed: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, value: T) {
+        self.lock().heap.push(value);
+        self.pushed.notify_one();
+    }
+
+    // pushes everything under a single lock
+    pub fn push_all<I: IntoIterator<Item = T>>(&self, iter: I) {
+        let added = {
+            let mut state = self.lock();
+            let len = state.heap.len();
+            state.heap.extend(iter);
+            state.heap.len() - len
+        };
+        if added == 1 {
+            self.pushed.notify_one();
+        } else if added > 1 {
+            self.pushed.notify_all();
+        }
+    }
+
+    // returns immediately, even if the queue is empty
+    pub fn pop(&self) -> Option<T> {
+        self.lock().heap.pop()
+    }
+
+    // blocks until an item is available, returns `None` once the queue is
+    // closed and empty
+    pub fn pop_wait(&self) -> Option<T> {
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.heap.pop() {
+                return Some(value);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.pushed.wait(state).unwrap();
+        }
+    }
+
+    // like `pop_wait`, but gives up once `timeout` has passed
+    pub fn pop_wait_timeout(&self, timeout: Duration) -> Option<T> {
+        // a deadline too far out for `Instant` is as good as none
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return self.pop_wait(),
+        };
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.heap.pop() {
+                return Some(value);
+            }
+            // wake-ups can be spurious, so wait for whatever is left of the timeout
+            let now = Instant::now();
+            if state.closed || now >= deadline {
+                return None;
+            }
+            state = self.pushed.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    // wakes up all waiting consumers, they drain the queue and then get `None`;
+    // pushing is still allowed afterwards
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.pushed.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    pub fn peek_with<R>(&self, f: impl FnOnce(Option<&T>) -> R) -> R {
+        f(self.lock().heap.peek())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().heap.is_empty()
+    }
+
+    pub fn into_heap(self) -> BinaryHeap<T, F> {
+        self.state.into_inner().unwrap().heap
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T, F>> {
+        self.state.lock().unwrap()
+    }
+}
@@ -0,0 +1,387 @@
+use crate::hasher::{splitmix64, SeedableHasher, SeededState, WyHasher};
+use crate::hashtable::INITIAL_BASE_SIZE;
+use crate::prime::next_prime;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::ops::Index;
+
+/// Cuckoo hash table: every key has one bucket in each of two arrays, picked
+/// by two differently seeded hash functions, and lives in one of them.
+///
+/// Lookups check two buckets and the stash, so they take constant time in the
+/// worst case. An insert that finds both buckets taken evicts one of the items,
+/// which moves to its bucket in the other array and may evict another one in
+/// turn. After a bounded number of evictions the insert gives up, assuming a
+/// cycle, and the last homeless item goes to the stash if it has room, or all
+/// items are rehashed with new seeds. The table grows once the arrays are 45%
+/// full, as two choices per key stop working well around half.
+pub struct CuckooHashTable<K: Eq + Hash, V, H: SeedableHasher = WyHasher> {
+    arrays: [Vec<Option<(K, V)>>; 2],
+    states: [SeededState<H>; 2],
+    stash: Vec<(K, V)>,
+    stash_limit: usize,
+    items: usize,
+    // where new seeds come from
+    seeds: u64,
+    rehashes: usize,
+}
+
+// rounds of new seeds before `rebuild` gives up, the arrays double every 4 of them
+const MAX_REBUILD_ATTEMPTS: usize = 16;
+
+// number of buckets per array to hold `items` items without going above 45% load
+fn buckets_for(items: usize) -> usize {
+    (items * 100 / 45 / 2 + 1).max(INITIAL_BASE_SIZE)
+}
+
+impl<K: Eq + Hash, V> Default for CuckooHashTable<K, V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<K: Eq + Hash, V> CuckooHashTable<K, V> {
+    // room for `capacity` items before the table has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_stash(capacity, 0)
+    }
+
+    // keeps up to `stash_limit` items that found no bucket, before rehashing
+    pub fn with_stash(stash_limit: usize) -> Self {
+        Self::with_capacity_and_stash(0, stash_limit)
+    }
+}
+
+impl<K: Eq + Hash, V, H: SeedableHasher> CuckooHashTable<K, V, H> {
+    pub fn with_capacity_and_stash(capacity: usize, stash_limit: usize) -> Self {
+        // every table draws its own seeds, so keys that cycle in one table do
+        // not cycle in all of them
+        let mut seeds = RandomState::new().hash_one(0);
+        let states = [
+            SeededState::new(splitmix64(&mut seeds)),
+            SeededState::new(splitmix64(&mut seeds)),
+        ];
+        let size = next_prime(buckets_for(capacity));
+        Self {
+            arrays: [empty_array(size), empty_array(size)],
+            states,
+            stash: Vec::with_capacity(stash_limit),
+            stash_limit,
+            items: 0,
+            seeds,
+            rehashes: 0,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if let Some(old) = self.get_mut(&key) {
+            *old = value;
+            return;
+        }
+        // if load is about to go above 0.45, resize
+        if self.items >= self.capacity() {
+            self.rebuild(self.array_len() * 2);
+        }
+
+        self.items += 1;
+        if let Err(homeless) = self.place((key, value)) {
+            self.stash.push(homeless);
+            if self.stash.len() > self.stash_limit {
+                self.rehashes += 1;
+                self.rebuild(self.array_len());
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match self.find(key)? {
+            Place::Array(side, idx) => self.arrays[side][idx].as_mut().map(|(_, value)| value),
+            Place::Stash(idx) => Some(&mut self.stash[idx].1),
+        }
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (key, value) = match self.find(key)? {
+            Place::Array(side, idx) => self.arrays[side][idx].as_ref().unwrap(),
+            Place::Stash(idx) => &self.stash[idx],
+        };
+        Some((key, value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let removed = match self.find(key)? {
+            Place::Array(side, idx) => self.arrays[side][idx].take().unwrap(),
+            Place::Stash(idx) => self.stash.swap_remove(idx),
+        };
+        self.items -= 1;
+
+        // if load is below 0.1, resize
+        if self.load() < 10 && self.array_len() / 2 >= INITIAL_BASE_SIZE {
+            self.rebuild(self.array_len() / 2);
+        }
+        Some(removed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    // removes all items but keeps the buckets
+    pub fn clear(&mut self) -> Vec<(K, V)> {
+        self.items = 0;
+        self.take_all()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.arrays
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(&self.stash)
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // number of items the table holds before it has to grow, it may still
+    // rehash with new seeds before that
+    pub fn capacity(&self) -> usize {
+        self.bucket_count() * 45 / 100
+    }
+
+    // buckets in both arrays, the table grows once 45% of them are used
+    pub fn bucket_count(&self) -> usize {
+        2 * self.array_len()
+    }
+
+    // items that found no bucket in the arrays
+    pub fn stash_len(&self) -> usize {
+        self.stash.len()
+    }
+
+    // how many times the items had to be rehashed with new seeds because a
+    // key found no bucket, growing and shrinking do not count
+    pub fn rehashes(&self) -> usize {
+        self.rehashes
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<Place>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        for side in 0..2 {
+            let idx = self.index(side, key);
+            if let Some((cur_key, _)) = &self.arrays[side][idx] {
+                if cur_key.borrow() == key {
+                    return Some(Place::Array(side, idx));
+                }
+            }
+        }
+        self.stash
+            .iter()
+            .position(|(cur_key, _)| cur_key.borrow() == key)
+            .map(Place::Stash)
+    }
+
+    // Puts the item into one of its buckets, evicting items from theirs as
+    // needed. Returns the item left without a bucket when the evictions run
+    // too long, which is most likely a cycle.
+    fn place(&mut self, mut item: (K, V)) -> Result<(), (K, V)> {
+        let max_evictions = 4 * (usize::BITS - self.array_len().leading_zeros()) as usize;
+        let mut side = 0;
+        for _ in 0..max_evictions {
+            let idx = self.index(side, &item.0);
+            match self.arrays[side][idx].replace(item) {
+                None => return Ok(()),
+                Some(evicted) => item = evicted,
+            }
+            // the evicted item moves to its bucket in the other array
+            side = 1 - side;
+        }
+        Err(item)
+    }
+
+    // Rehashes every item into arrays of `size` buckets, with new seeds until
+    // all of them fit. Repeated failures mean the arrays are too crowded for
+    // any seeds, so they are grown. Failing on and on means the keys collide
+    // whatever the seeds, as with a hasher that ignores them, so it panics
+    // rather than grow without end.
+    fn rebuild(&mut self, size: usize) {
+        let mut size = next_prime(size.max(INITIAL_BASE_SIZE));
+        let mut items = self.take_all();
+        let mut attempts = 0;
+        loop {
+            self.states = [
+                SeededState::new(splitmix64(&mut self.seeds)),
+                SeededState::new(splitmix64(&mut self.seeds)),
+            ];
+            self.arrays = [empty_array(size), empty_array(size)];
+
+            let mut failed = false;
+            while let Some(item) = items.pop() {
+                if let Err(homeless) = self.place(item) {
+                    if self.stash.len() < self.stash_limit {
+                        self.stash.push(homeless);
+                    } else {
+                        items.push(homeless);
+                        failed = true;
+                        break;
+                    }
+                }
+            }
+            if !failed {
+                return;
+            }
+
+            items.extend(self.take_all());
+            self.rehashes += 1;
+            attempts += 1;
+            assert!(
+                attempts < MAX_REBUILD_ATTEMPTS,
+                "cuckoo hashing keeps failing, the keys collide whatever the seeds"
+            );
+            if attempts % 4 == 0 {
+                size = next_prime(size * 2);
+            }
+        }
+    }
+
+    // empties the arrays and the stash, without changing the item count
+    fn take_all(&mut self) -> Vec<(K, V)> {
+        let mut items: Vec<_> = self
+            .arrays
+            .iter_mut()
+            .flat_map(|array| array.iter_mut().filter_map(Option::take))
+            .collect();
+        items.append(&mut self.stash);
+        items
+    }
+
+    fn index<Q>(&self, side: usize, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        (self.states[side].hash_one(key) % self.array_len() as u64) as usize
+    }
+
+    fn array_len(&self) -> usize {
+        self.arrays[0].len()
+    }
+
+    fn load(&self) -> usize {
+        self.items * 100 / self.bucket_count()
+    }
+}
+
+enum Place {
+    Array(usize, usize),
+    Stash(usize),
+}
+
+fn empty_array<K, V>(size: usize) -> Vec<Option<(K, V)>> {
+    let mut array = Vec::new();
+    array.resize_with(size, || None);
+    array
+}
+
+impl<K, V, H> Debug for CuckooHashTable<K, V, H>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    H: SeedableHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// panics if the key is not in the table
+impl<K, Q, V, H> Index<&Q> for CuckooHashTable<K, V, H>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    H: SeedableHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Eq + Hash, V, H: SeedableHasher> Extend<(K, V)> for CuckooHashTable<K, V, H> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, H: SeedableHasher> FromIterator<(K, V)> for CuckooHashTable<K, V, H> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::with_capacity_and_stash(0, 0);
+        m.extend(iter);
+        m
+    }
+}
+
+impl<K: Eq + Hash, V, H: SeedableHasher> IntoIterator for CuckooHashTable<K, V, H> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.take_all().into_iter()
+    }
+}
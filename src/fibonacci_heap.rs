@@ -0,0 +1,330 @@
+use crate::heap_handle::{self, HeapId};
+
+use std::cell::{Ref, RefCell};
+use std::cmp::Ordering;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+use node::Node;
+
+// The root list and the children of every node are doubly linked lists, where
+// each node owns its right neighbour and keeps a weak link to its left one.
+
+type NodeRef<T> = Rc<RefCell<Node<T>>>;
+
+// public only so that `Handle` can name it, the module keeps it out of reach
+mod node {
+    use super::{List, NodeRef};
+    use std::cell::RefCell;
+    use std::rc::Weak;
+
+    pub struct Node<T> {
+        pub(super) value: T,
+        pub(super) parent: Weak<RefCell<Node<T>>>,
+        pub(super) children: List<T>,
+        pub(super) next: Option<NodeRef<T>>,
+        pub(super) prev: Weak<RefCell<Node<T>>>,
+        // lost a child since it became a child itself
+        pub(super) marked: bool,
+    }
+}
+
+struct List<T> {
+    head: Option<NodeRef<T>>,
+    tail: Weak<RefCell<Node<T>>>,
+    len: usize,
+}
+
+impl<T> List<T> {
+    fn new() -> Self {
+        Self {
+            head: None,
+            tail: Weak::new(),
+            len: 0,
+        }
+    }
+
+    fn push_back(&mut self, node: NodeRef<T>) {
+        {
+            let mut node = node.borrow_mut();
+            node.next = None;
+            node.prev = self.tail.clone();
+        }
+        let tail = mem::replace(&mut self.tail, Rc::downgrade(&node));
+        match tail.upgrade() {
+            Some(tail) => tail.borrow_mut().next = Some(node),
+            None => self.head = Some(node),
+        }
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<NodeRef<T>> {
+        let head = self.head.take()?;
+        match head.borrow_mut().next.take() {
+            Some(next) => {
+                next.borrow_mut().prev = Weak::new();
+                self.head = Some(next);
+            }
+            None => self.tail = Weak::new(),
+        }
+        self.len -= 1;
+        Some(head)
+    }
+
+    fn append(&mut self, other: &mut List<T>) {
+        let head = match other.head.take() {
+            Some(head) => head,
+            None => return,
+        };
+        match self.tail.upgrade() {
+            Some(tail) => {
+                head.borrow_mut().prev = Rc::downgrade(&tail);
+                tail.borrow_mut().next = Some(head);
+            }
+            None => self.head = Some(head),
+        }
+        self.tail = mem::replace(&mut other.tail, Weak::new());
+        self.len += mem::replace(&mut other.len, 0);
+    }
+
+    fn remove(&mut self, node: &NodeRef<T>) {
+        let (prev, next) = {
+            let mut node = node.borrow_mut();
+            let prev = mem::replace(&mut node.prev, Weak::new());
+            (prev, node.next.take())
+        };
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev.clone(),
+            None => self.tail = prev.clone(),
+        }
+        match prev.upgrade() {
+            Some(prev) => prev.borrow_mut().next = next,
+            None => self.head = next,
+        }
+        self.len -= 1;
+    }
+}
+
+impl<T> Drop for List<T> {
+    // lists and trees can be as long as the heap, so drop the nodes without recursion
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self.head.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut node = node.borrow_mut();
+            stack.extend(node.next.take());
+            stack.extend(node.children.head.take());
+        }
+    }
+}
+
+pub type Handle<T> = heap_handle::Handle<Node<T>>;
+
+/// Fibonacci heap with O(1) `push` and `meld`, amortized O(1) `decrease_key`
+/// and amortized O(log n) `pop`.
+///
+/// Items are ordered by `cmp` the same way as in `heap::BinaryHeap`.
+pub struct FibonacciHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    roots: List<T>,
+    top: Option<NodeRef<T>>,
+    len: usize,
+    id: HeapId,
+    cmp: F,
+}
+
+impl<T: Ord> FibonacciHeap<T, fn(&T, &T) -> Ordering> {
+    pub fn min() -> Self {
+        Self::new(|a, b| a.cmp(b))
+    }
+
+    pub fn max() -> Self {
+        Self::new(|a, b| b.cmp(a))
+    }
+}
+
+impl<T, F> FibonacciHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    pub fn new(cmp: F) -> Self {
+        Self {
+            roots: List::new(),
+            top: None,
+            len: 0,
+            id: HeapId::new(),
+            cmp,
+        }
+    }
+
+    pub fn push(&mut self, value: T) -> Handle<T> {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            parent: Weak::new(),
+            children: List::new(),
+            next: None,
+            prev: Weak::new(),
+            marked: false,
+        }));
+        let handle = Handle {
+            node: Rc::downgrade(&node),
+            heap: self.id.clone(),
+        };
+        self.update_top(&node);
+        self.roots.push_back(node);
+        self.len += 1;
+        handle
+    }
+
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        self.top
+            .as_ref()
+            .map(|top| Ref::map(top.borrow(), |node| &node.value))
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let top = self.top.take()?;
+        self.roots.remove(&top);
+
+        let mut children = mem::replace(&mut top.borrow_mut().children, List::new());
+        let mut child = children.head.clone();
+        while let Some(node) = child {
+            let mut node = node.borrow_mut();
+            node.parent = Weak::new();
+            child = node.next.clone();
+        }
+        self.roots.append(&mut children);
+        self.len -= 1;
+        self.consolidate();
+
+        // handles are weak, so the heap holds the only strong reference
+        match Rc::try_unwrap(top) {
+            Ok(node) => Some(node.into_inner().value),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    // moves all items of `other` into `self`, ordering them by `self`'s comparator
+    pub fn meld(&mut self, mut other: Self) {
+        if let Some(top) = other.top.take() {
+            self.update_top(&top);
+        }
+        self.roots.append(&mut other.roots);
+        self.len += mem::replace(&mut other.len, 0);
+        self.id.absorb(&other.id);
+    }
+
+    // `handle` has to come from this heap or from a heap melded into it
+    pub fn decrease_key(&mut self, handle: &Handle<T>, value: T) {
+        assert!(
+            self.id.contains(&handle.heap),
+            "handle is from another heap"
+        );
+        let node = handle
+            .node
+            .upgrade()
+            .expect("item is no longer in the heap");
+        {
+            let mut node = node.borrow_mut();
+            assert!(
+                (self.cmp)(&value, &node.value) != Ordering::Greater,
+                "new key ranks after the current one"
+            );
+            node.value = value;
+        }
+
+        let parent = node.borrow().parent.upgrade();
+        if let Some(parent) = parent {
+            if (self.cmp)(&node.borrow().value, &parent.borrow().value) == Ordering::Less {
+                self.cut(&node, &parent);
+                self.cascading_cut(parent);
+            }
+        }
+        self.update_top(&node);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // `node` has to be a root
+    fn update_top(&mut self, node: &NodeRef<T>) {
+        let is_top = match &self.top {
+            Some(top) => (self.cmp)(&node.borrow().value, &top.borrow().value) == Ordering::Less,
+            None => true,
+        };
+        if is_top {
+            self.top = Some(Rc::clone(node));
+        }
+    }
+
+    // links roots of equal degree until all degrees are distinct
+    fn consolidate(&mut self) {
+        let mut by_degree: Vec<Option<NodeRef<T>>> = Vec::new();
+        while let Some(mut tree) = self.roots.pop_front() {
+            loop {
+                let degree = tree.borrow().children.len;
+                if degree >= by_degree.len() {
+                    by_degree.resize_with(degree + 1, || None);
+                }
+                match by_degree[degree].take() {
+                    Some(other) => tree = self.link(tree, other),
+                    None => {
+                        by_degree[degree] = Some(tree);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for tree in by_degree.into_iter().flatten() {
+            self.update_top(&tree);
+            self.roots.push_back(tree);
+        }
+    }
+
+    // makes the root that ranks after the other one a child of the other
+    fn link(&self, a: NodeRef<T>, b: NodeRef<T>) -> NodeRef<T> {
+        let a_first = (self.cmp)(&a.borrow().value, &b.borrow().value) != Ordering::Greater;
+        let (parent, child) = if a_first { (a, b) } else { (b, a) };
+        {
+            let mut child = child.borrow_mut();
+            child.parent = Rc::downgrade(&parent);
+            child.marked = false;
+        }
+        parent.borrow_mut().children.push_back(child);
+        parent
+    }
+
+    // moves `node` from the children of `parent` to the root list
+    fn cut(&mut self, node: &NodeRef<T>, parent: &NodeRef<T>) {
+        parent.borrow_mut().children.remove(node);
+        {
+            let mut node = node.borrow_mut();
+            node.parent = Weak::new();
+            node.marked = false;
+        }
+        self.roots.push_back(Rc::clone(node));
+    }
+
+    // a node that loses its second child is cut as well, all the way up
+    fn cascading_cut(&mut self, mut node: NodeRef<T>) {
+        loop {
+            let parent = match node.borrow().parent.upgrade() {
+                Some(parent) => parent,
+                None => return,
+            };
+            if !node.borrow().marked {
+                node.borrow_mut().marked = true;
+                return;
+            }
+            self.cut(&node, &parent);
+            node = parent;
+        }
+    }
+}
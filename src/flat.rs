@@ -0,0 +1,482 @@
+use crate::hashtable::DefaultState;
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Index;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use sse2::Group;
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+use portable::Group;
+
+// slots per group, one control byte each
+const GROUP: usize = 16;
+const MIN_GROUPS: usize = 4;
+
+// A control byte is one of these two, or the 7-bit fingerprint of the item in
+// the slot. Both special values have the top bit set and fingerprints do not.
+const EMPTY: u8 = 0b1111_1111;
+const DELETED: u8 = 0b1000_0000;
+
+/// Open-addressing hash table in the style of SwissTable.
+///
+/// Besides the slots there is an array with a control byte per slot, which
+/// says whether the slot is empty, deleted, or holds an item, and then has 7
+/// bits of that item's hash. Lookups go through the control bytes a group of
+/// 16 at a time, with SSE2 on x86_64, and only look at the slots whose
+/// fingerprint matches. Groups are probed with triangular steps, as their
+/// number is a power of two. The table grows once 7/8 of the slots are taken.
+pub struct FlatHashTable<K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    ctrl: Vec<u8>,
+    slots: Vec<Option<(K, V)>>,
+    items: usize,
+    // slots that can still go from empty to taken before the table is rehashed
+    growth_left: usize,
+    hash_builder: S,
+}
+
+// number of groups to hold `items` items without going above 7/8 load
+fn groups_for(items: usize) -> usize {
+    (items * 8 / 7 / GROUP + 1)
+        .next_power_of_two()
+        .max(MIN_GROUPS)
+}
+
+impl<K: Eq + Hash, V> Default for FlatHashTable<K, V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<K: Eq + Hash, V> FlatHashTable<K, V> {
+    // room for `capacity` items before the table has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultState::default())
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> FlatHashTable<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut m = Self {
+            ctrl: Vec::new(),
+            slots: Vec::new(),
+            items: 0,
+            growth_left: 0,
+            hash_builder,
+        };
+        m.reset(groups_for(capacity));
+        m
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let hash = self.hash_builder.hash_one(&key);
+        if let Some(idx) = self.find(hash, &key) {
+            self.slots[idx] = Some((key, value));
+            return;
+        }
+        if self.growth_left == 0 {
+            // grow when full of items, rehash in place when full of tombstones
+            let groups = self.ctrl.len() / GROUP;
+            if self.items >= max_items(groups) / 2 {
+                self.rehash(groups * 2);
+            } else {
+                self.rehash(groups);
+            }
+        }
+        self.insert_new(hash, key, value);
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[idx].as_mut().map(|(_, value)| value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[idx].as_ref().map(|(key, value)| (key, value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.find(self.hash_builder.hash_one(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(self.hash_builder.hash_one(key), key)?;
+        let start = idx / GROUP * GROUP;
+        // A probe only moves past a group without empty slots, so if this
+        // group has one no probe goes through it and the slot can be empty again.
+        if Group::load(&self.ctrl[start..start + GROUP])
+            .match_byte(EMPTY)
+            .any()
+        {
+            self.ctrl[idx] = EMPTY;
+            self.growth_left += 1;
+        } else {
+            self.ctrl[idx] = DELETED;
+        }
+        self.items -= 1;
+        self.slots[idx].take()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    // removes all items but keeps the slots
+    pub fn clear(&mut self) -> Vec<(K, V)> {
+        let items = self.slots.iter_mut().filter_map(Option::take).collect();
+        self.reset(self.ctrl.len() / GROUP);
+        items
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // number of items the table holds before it has to grow or drop its tombstones
+    pub fn capacity(&self) -> usize {
+        max_items(self.ctrl.len() / GROUP)
+    }
+
+    // number of slots, the table grows once 7/8 of them are used
+    pub fn bucket_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let fingerprint = fingerprint(hash);
+        for start in self.probe(hash) {
+            let group = Group::load(&self.ctrl[start..start + GROUP]);
+            for offset in group.match_byte(fingerprint) {
+                if let Some((cur_key, _)) = &self.slots[start + offset] {
+                    if cur_key.borrow() == key {
+                        return Some(start + offset);
+                    }
+                }
+            }
+            if group.match_byte(EMPTY).any() {
+                return None;
+            }
+        }
+        None
+    }
+
+    // the key must not be in the table yet
+    fn insert_new(&mut self, hash: u64, key: K, value: V) {
+        let idx = self
+            .probe(hash)
+            .find_map(|start| {
+                let group = Group::load(&self.ctrl[start..start + GROUP]);
+                group
+                    .match_empty_or_deleted()
+                    .lowest()
+                    .map(|offset| start + offset)
+            })
+            .unwrap();
+        if self.ctrl[idx] == EMPTY {
+            self.growth_left -= 1;
+        }
+        self.ctrl[idx] = fingerprint(hash);
+        self.slots[idx] = Some((key, value));
+        self.items += 1;
+    }
+
+    // first slots of the groups to look at for `hash`, every group comes up once
+    fn probe(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let groups = self.ctrl.len() / GROUP;
+        let mut pos = (hash >> 7) as usize & (groups - 1);
+        (0..groups).map(move |step| {
+            pos = (pos + step) & (groups - 1);
+            pos * GROUP
+        })
+    }
+
+    // reinserts every item into `groups` groups, dropping all tombstones
+    fn rehash(&mut self, groups: usize) {
+        let items: Vec<_> = self.slots.iter_mut().filter_map(Option::take).collect();
+        self.reset(groups);
+        for (key, value) in items {
+            let hash = self.hash_builder.hash_one(&key);
+            self.insert_new(hash, key, value);
+        }
+    }
+
+    fn reset(&mut self, groups: usize) {
+        self.ctrl = vec![EMPTY; groups * GROUP];
+        self.slots.clear();
+        self.slots.resize_with(groups * GROUP, || None);
+        self.items = 0;
+        self.growth_left = max_items(groups);
+    }
+}
+
+fn max_items(groups: usize) -> usize {
+    groups * GROUP * 7 / 8
+}
+
+// the low 7 bits of the hash, the rest picks the first group
+fn fingerprint(hash: u64) -> u8 {
+    (hash & 0x7f) as u8
+}
+
+// Offsets in a group, as the bits set in a 16-bit mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BitMask(u16);
+
+impl BitMask {
+    fn any(self) -> bool {
+        self.0 != 0
+    }
+
+    fn lowest(self) -> Option<usize> {
+        if self.any() {
+            Some(self.0.trailing_zeros() as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let lowest = self.lowest()?;
+        self.0 &= self.0 - 1;
+        Some(lowest)
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+    use super::{BitMask, GROUP};
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+
+    // the control bytes of a group in one SSE register
+    pub(super) struct Group(__m128i);
+
+    impl Group {
+        pub(super) fn load(ctrl: &[u8]) -> Self {
+            assert!(ctrl.len() >= GROUP);
+            // SAFETY: the slice has 16 bytes to read, and the load does not need alignment
+            Group(unsafe { _mm_loadu_si128(ctrl.as_ptr() as *const __m128i) })
+        }
+
+        pub(super) fn match_byte(&self, byte: u8) -> BitMask {
+            // SAFETY: SSE2 is part of every x86_64 target, as the cfg on this module checks
+            let bits = unsafe {
+                let equal = _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8));
+                _mm_movemask_epi8(equal)
+            };
+            BitMask(bits as u16)
+        }
+
+        // the special control bytes are exactly those with the top bit set
+        pub(super) fn match_empty_or_deleted(&self) -> BitMask {
+            // SAFETY: as in `match_byte`
+            BitMask(unsafe { _mm_movemask_epi8(self.0) } as u16)
+        }
+    }
+}
+
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "sse2"), allow(dead_code))]
+mod portable {
+    use super::{BitMask, GROUP};
+    use std::convert::TryInto;
+
+    pub(super) struct Group([u8; GROUP]);
+
+    impl Group {
+        pub(super) fn load(ctrl: &[u8]) -> Self {
+            Group(ctrl[..GROUP].try_into().unwrap())
+        }
+
+        pub(super) fn match_byte(&self, byte: u8) -> BitMask {
+            self.mask(|ctrl| ctrl == byte)
+        }
+
+        pub(super) fn match_empty_or_deleted(&self) -> BitMask {
+            self.mask(|ctrl| ctrl & 0x80 != 0)
+        }
+
+        fn mask(&self, f: impl Fn(u8) -> bool) -> BitMask {
+            let bits = self
+                .0
+                .iter()
+                .enumerate()
+                .filter(|&(_, &ctrl)| f(ctrl))
+                .fold(0, |bits, (offset, _)| bits | 1 << offset);
+            BitMask(bits)
+        }
+    }
+}
+
+impl<K, V, S> Debug for FlatHashTable<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> Clone for FlatHashTable<K, V, S>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            ctrl: self.ctrl.clone(),
+            slots: self.slots.clone(),
+            items: self.items,
+            growth_left: self.growth_left,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+// panics if the key is not in the table
+impl<K, Q, V, S> Index<&Q> for FlatHashTable<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for FlatHashTable<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for FlatHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::with_hasher(S::default());
+        m.extend(iter);
+        m
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> IntoIterator for FlatHashTable<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Option<(K, V)>>>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut self.slots).into_iter().flatten()
+    }
+}
+
+#[test]
+fn test_group_matching() {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let ctrl: Vec<u8> = (0..GROUP)
+            .map(|_| match rng.gen_range(0, 4) {
+                0 => EMPTY,
+                1 => DELETED,
+                _ => rng.gen_range(0, 4),
+            })
+            .collect();
+        let byte = rng.gen_range(0, 4);
+        let expected = |f: &dyn Fn(u8) -> bool| {
+            (0..GROUP)
+                .filter(|&offset| f(ctrl[offset]))
+                .collect::<Vec<_>>()
+        };
+
+        let portable = portable::Group::load(&ctrl);
+        let group = Group::load(&ctrl);
+        for matched in [portable.match_byte(byte), group.match_byte(byte)] {
+            assert_eq!(matched.collect::<Vec<_>>(), expected(&|ctrl| ctrl == byte));
+        }
+        for matched in [
+            portable.match_empty_or_deleted(),
+            group.match_empty_or_deleted(),
+        ] {
+            assert_eq!(
+                matched.collect::<Vec<_>>(),
+                expected(&|ctrl| ctrl == EMPTY || ctrl == DELETED)
+            );
+        }
+    }
+}
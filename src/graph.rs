@@ -5,9 +5,44 @@ use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashSet, VecDeque};
 use std::hash::Hash;
 
+use crate::radix_heap::RadixHeap;
+
 pub type NodeIndex = usize;
 pub type EdgeIndex = usize;
 
+// Priority queue used by Dijkstra's algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapKind {
+    Binary,
+    // faster for integer weights, distances only grow during the search
+    Radix,
+}
+
+trait DistanceQueue {
+    fn push(&mut self, dist: u64, node: NodeIndex);
+    fn pop(&mut self) -> Option<(u64, NodeIndex)>;
+}
+
+impl DistanceQueue for BinaryHeap<(Reverse<u64>, NodeIndex)> {
+    fn push(&mut self, dist: u64, node: NodeIndex) {
+        BinaryHeap::push(self, (Reverse(dist), node))
+    }
+
+    fn pop(&mut self) -> Option<(u64, NodeIndex)> {
+        BinaryHeap::pop(self).map(|(Reverse(dist), node)| (dist, node))
+    }
+}
+
+impl DistanceQueue for RadixHeap<NodeIndex> {
+    fn push(&mut self, dist: u64, node: NodeIndex) {
+        RadixHeap::push(self, dist, node)
+    }
+
+    fn pop(&mut self) -> Option<(u64, NodeIndex)> {
+        RadixHeap::pop(self)
+    }
+}
+
 pub struct NodeData<T: Eq + Hash> {
     first_outgoing_edge: Option<EdgeIndex>,
     value: T,
@@ -73,7 +108,7 @@ impl<T: Eq + Hash> Graph<T> {
             if seen.contains(&node) {
                 continue;
             }
-            f(&self.get_value(node));
+            f(self.get_value(node));
             seen.insert(node);
 
             for (n, _) in self.successors(node) {
@@ -94,7 +129,7 @@ impl<T: Eq + Hash> Graph<T> {
             if seen.contains(&node) {
                 continue;
             }
-            f(&self.get_value(node));
+            f(self.get_value(node));
             seen.insert(node);
 
             let successors: Vec<_> = self.successors(node).collect();
@@ -104,22 +139,45 @@ impl<T: Eq + Hash> Graph<T> {
         }
     }
 
-    pub fn dijkstra(&self, source_node: NodeIndex, edge_weights: &[u64]) -> Vec<u64> {
+    pub fn dijkstra(
+        &self,
+        source_node: NodeIndex,
+        edge_weights: &[u64],
+        heap: HeapKind,
+    ) -> Vec<u64> {
+        match heap {
+            HeapKind::Binary => {
+                self.dijkstra_with_queue(source_node, edge_weights, BinaryHeap::new())
+            }
+            HeapKind::Radix => {
+                self.dijkstra_with_queue(source_node, edge_weights, RadixHeap::default())
+            }
+        }
+    }
+
+    fn dijkstra_with_queue<Q>(
+        &self,
+        source_node: NodeIndex,
+        edge_weights: &[u64],
+        mut heap: Q,
+    ) -> Vec<u64>
+    where
+        Q: DistanceQueue,
+    {
         // distances from node to source
-        let mut dist_vec = vec![u64::max_value(); self.nodes.len()];
+        let mut dist_vec = vec![u64::MAX; self.nodes.len()];
         dist_vec[source_node] = 0;
 
-        let mut heap = BinaryHeap::new();
-        heap.push((Reverse(dist_vec[source_node]), source_node));
+        heap.push(dist_vec[source_node], source_node);
 
-        while let Some((Reverse(dist), cur_node)) = heap.pop() {
+        while let Some((dist, cur_node)) = heap.pop() {
             if dist_vec[cur_node] == dist {
                 for (node, edge) in self.successors(cur_node) {
                     // Calculate Dijkstra's greedy score
                     let tent_dist = dist + edge_weights[edge];
                     if tent_dist < dist_vec[node] {
                         dist_vec[node] = tent_dist;
-                        heap.push((Reverse(tent_dist), node));
+                        heap.push(tent_dist, node);
                     }
                 }
             }
@@ -134,7 +192,7 @@ impl<T: Eq + Hash> Graph<T> {
         edge_weights: &[u64],
     ) -> Option<Vec<NodeIndex>> {
         // distances from node to source
-        let mut dist_vec = vec![u64::max_value(); self.nodes.len()];
+        let mut dist_vec = vec![u64::MAX; self.nodes.len()];
         let mut parent = vec![None; self.nodes.len()];
         let mut heap = BinaryHeap::new();
 
@@ -250,7 +308,35 @@ mod tests {
             weights.append(&mut vec![weight, weight]);
         }
 
-        assert_eq!(vec![0, 8, 3, 4, 7, 6, 12, 5], graph.dijkstra(a, &weights))
+        for heap in [HeapKind::Binary, HeapKind::Radix] {
+            assert_eq!(
+                vec![0, 8, 3, 4, 7, 6, 12, 5],
+                graph.dijkstra(a, &weights, heap),
+                "{:?}",
+                heap
+            );
+        }
+    }
+
+    #[test]
+    fn graph_dijkstra_heaps_agree() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let mut graph = Graph::default();
+        let nodes = (0..200).map(|i| graph.add_node(i)).collect::<Vec<_>>();
+        let mut weights = Vec::new();
+        for _ in 0..1000 {
+            let source = nodes[rng.gen_range(0, nodes.len())];
+            let target = nodes[rng.gen_range(0, nodes.len())];
+            graph.add_edge(source, target);
+            weights.push(rng.gen_range(0, 1_000_000));
+        }
+
+        assert_eq!(
+            graph.dijkstra(nodes[0], &weights, HeapKind::Binary),
+            graph.dijkstra(nodes[0], &weights, HeapKind::Radix)
+        );
     }
 
     #[test]
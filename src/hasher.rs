@@ -0,0 +1,640 @@
+use std::convert::TryInto;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::marker::PhantomData;
+
+// Hashers for `HashTable` and friends. `HasherDJB2` and `FnvHasher` work byte by
+// byte, the others on blocks of 8 to 48 bytes. Those keep the bytes of a block
+// that is not complete yet in a fixed buffer, until the rest of it is written
+// or `finish` takes it as the tail of the input.
+
+/// Hashers whose output can be changed with a seed, e.g. to rehash a table
+/// with a fresh hash function.
+pub trait SeedableHasher: Hasher {
+    fn with_seed(seed: u64) -> Self;
+}
+
+/// Builds `H` hashers that all use the same seed.
+pub struct SeededState<H> {
+    seed: u64,
+    hasher: PhantomData<fn() -> H>,
+}
+
+impl<H: SeedableHasher> SeededState<H> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl<H> Clone for SeededState<H> {
+    fn clone(&self) -> Self {
+        Self {
+            seed: self.seed,
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<H: SeedableHasher> Default for SeededState<H> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<H: SeedableHasher> BuildHasher for SeededState<H> {
+    type Hasher = H;
+
+    fn build_hasher(&self) -> H {
+        H::with_seed(self.seed)
+    }
+}
+
+// D. J. Bernstein hash function
+// http://cr.yp.to/cdb/cdb.txt
+pub struct HasherDJB2 {
+    hash: u64,
+}
+
+impl Default for HasherDJB2 {
+    fn default() -> Self {
+        Self { hash: 5381u64 }
+    }
+}
+
+impl Hasher for HasherDJB2 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash << 5)
+                .wrapping_add(self.hash)
+                .wrapping_add(*byte as u64);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// 64-bit FNV-1a
+// http://www.isthe.com/chongo/tech/comp/fnv/index.html
+pub struct FnvHasher {
+    hash: u64,
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self {
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// MurmurHash3, the x64 128-bit variant, of which `finish` returns the first half
+// https://github.com/aappleby/smhasher/blob/master/src/MurmurHash3.cpp
+pub struct Murmur3Hasher {
+    h1: u64,
+    h2: u64,
+    len: u64,
+    tail: Buffer<16>,
+}
+
+impl Default for Murmur3Hasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl SeedableHasher for Murmur3Hasher {
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            h1: seed,
+            h2: seed,
+            len: 0,
+            tail: Buffer::new(),
+        }
+    }
+}
+
+const MURMUR_C1: u64 = 0x87c3_7b91_1142_53d5;
+const MURMUR_C2: u64 = 0x4cf5_ad43_2745_937f;
+
+fn murmur_k1(k1: u64) -> u64 {
+    k1.wrapping_mul(MURMUR_C1)
+        .rotate_left(31)
+        .wrapping_mul(MURMUR_C2)
+}
+
+fn murmur_k2(k2: u64) -> u64 {
+    k2.wrapping_mul(MURMUR_C2)
+        .rotate_left(33)
+        .wrapping_mul(MURMUR_C1)
+}
+
+impl Murmur3Hasher {
+    pub fn finish128(&self) -> (u64, u64) {
+        let (mut h1, mut h2) = (self.h1, self.h2);
+        let tail = self.tail.as_slice();
+        if tail.len() > 8 {
+            h2 ^= murmur_k2(read_tail(&tail[8..]));
+        }
+        if !tail.is_empty() {
+            h1 ^= murmur_k1(read_tail(&tail[..tail.len().min(8)]));
+        }
+
+        h1 ^= self.len;
+        h2 ^= self.len;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = fmix64(h1);
+        h2 = fmix64(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        (h1, h2)
+    }
+
+    fn mix_block(&mut self, block: &[u8; 16]) {
+        self.h1 ^= murmur_k1(read_u64(&block[..8]));
+        self.h1 = self
+            .h1
+            .rotate_left(27)
+            .wrapping_add(self.h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+        self.h2 ^= murmur_k2(read_u64(&block[8..]));
+        self.h2 = self
+            .h2
+            .rotate_left(31)
+            .wrapping_add(self.h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+}
+
+impl Hasher for Murmur3Hasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        while !bytes.is_empty() {
+            bytes = self.tail.fill(bytes);
+            if let Some(block) = self.tail.take_full() {
+                self.mix_block(&block);
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.finish128().0
+    }
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+// XXH64
+// https://github.com/Cyan4973/xxHash/blob/dev/doc/xxhash_spec.md
+pub struct XxHasher64 {
+    seed: u64,
+    lanes: [u64; 4],
+    len: u64,
+    stripe: Buffer<32>,
+}
+
+impl Default for XxHasher64 {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl SeedableHasher for XxHasher64 {
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            lanes: [
+                seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+                seed.wrapping_add(XXH_PRIME_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME_1),
+            ],
+            len: 0,
+            stripe: Buffer::new(),
+        }
+    }
+}
+
+const XXH_PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH_PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH_PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH_PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH_PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn xxh_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME_1)
+}
+
+fn xxh_merge(acc: u64, lane: u64) -> u64 {
+    (acc ^ xxh_round(0, lane))
+        .wrapping_mul(XXH_PRIME_1)
+        .wrapping_add(XXH_PRIME_4)
+}
+
+impl Hasher for XxHasher64 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        while !bytes.is_empty() {
+            bytes = self.stripe.fill(bytes);
+            if let Some(stripe) = self.stripe.take_full() {
+                for (lane, input) in self.lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+                    *lane = xxh_round(*lane, read_u64(input));
+                }
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.len >= 32 {
+            let lanes = self.lanes;
+            let hash = lanes[0]
+                .rotate_left(1)
+                .wrapping_add(lanes[1].rotate_left(7))
+                .wrapping_add(lanes[2].rotate_left(12))
+                .wrapping_add(lanes[3].rotate_left(18));
+            lanes.iter().fold(hash, |hash, &lane| xxh_merge(hash, lane))
+        } else {
+            self.seed.wrapping_add(XXH_PRIME_5)
+        };
+        hash = hash.wrapping_add(self.len);
+
+        let mut rest = self.stripe.as_slice();
+        while rest.len() >= 8 {
+            hash ^= xxh_round(0, read_u64(&rest[..8]));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(XXH_PRIME_1)
+                .wrapping_add(XXH_PRIME_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            hash ^= read_tail(&rest[..4]).wrapping_mul(XXH_PRIME_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(XXH_PRIME_2)
+                .wrapping_add(XXH_PRIME_3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            hash ^= (byte as u64).wrapping_mul(XXH_PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XXH_PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XXH_PRIME_3);
+        hash ^ (hash >> 32)
+    }
+}
+
+// wyhash, final version 4
+// https://github.com/wangyi-fudan/wyhash
+//
+// The last 48-byte block is mixed in like the ones before only if more input
+// follows it, so a full buffer waits for the next write. The final step reads
+// the last 16 bytes of the input, which may reach back into a mixed block.
+pub struct WyHasher {
+    seed: u64,
+    see1: u64,
+    see2: u64,
+    len: u64,
+    block: Buffer<48>,
+    // the end of the last mixed block
+    mixed_tail: [u8; 16],
+}
+
+impl Default for WyHasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl SeedableHasher for WyHasher {
+    fn with_seed(seed: u64) -> Self {
+        let seed = seed ^ wymix(seed ^ WY_SECRET[0], WY_SECRET[1]);
+        Self {
+            seed,
+            see1: seed,
+            see2: seed,
+            len: 0,
+            block: Buffer::new(),
+            mixed_tail: [0; 16],
+        }
+    }
+}
+
+const WY_SECRET: [u64; 4] = [
+    0x2d35_8dcc_aa6c_78a5,
+    0x8bb8_4b93_962e_acc9,
+    0x4b33_a62e_d433_d4a3,
+    0x4d5a_2da5_1de1_aa47,
+];
+
+// the halves of the full 128-bit product
+fn wymum(a: u64, b: u64) -> (u64, u64) {
+    let product = a as u128 * b as u128;
+    (product as u64, (product >> 64) as u64)
+}
+
+fn wymix(a: u64, b: u64) -> u64 {
+    let (low, high) = wymum(a, b);
+    low ^ high
+}
+
+impl WyHasher {
+    fn mix_block(&mut self, block: &[u8; 48]) {
+        self.seed = wymix(
+            read_u64(&block[..8]) ^ WY_SECRET[1],
+            read_u64(&block[8..16]) ^ self.seed,
+        );
+        self.see1 = wymix(
+            read_u64(&block[16..24]) ^ WY_SECRET[2],
+            read_u64(&block[24..32]) ^ self.see1,
+        );
+        self.see2 = wymix(
+            read_u64(&block[32..40]) ^ WY_SECRET[3],
+            read_u64(&block[40..48]) ^ self.see2,
+        );
+        self.mixed_tail.copy_from_slice(&block[32..]);
+    }
+}
+
+impl Hasher for WyHasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        while !bytes.is_empty() {
+            // more input follows, so a full block is not the last one
+            if let Some(block) = self.block.take_full() {
+                self.mix_block(&block);
+            }
+            bytes = self.block.fill(bytes);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let len = self.len;
+        let bytes = self.block.as_slice();
+        let mut seed = self.seed;
+
+        let (a, b) = if len <= 16 {
+            // nothing was mixed in, the whole input is in the buffer
+            let len = bytes.len();
+            if len >= 4 {
+                // two possibly overlapping reads from each end
+                let offset = (len >> 3) << 2;
+                (
+                    (read_tail(&bytes[..4]) << 32) | read_tail(&bytes[offset..offset + 4]),
+                    (read_tail(&bytes[len - 4..]) << 32)
+                        | read_tail(&bytes[len - 4 - offset..len - offset]),
+                )
+            } else if len > 0 {
+                let a = ((bytes[0] as u64) << 16)
+                    | ((bytes[len >> 1] as u64) << 8)
+                    | bytes[len - 1] as u64;
+                (a, 0)
+            } else {
+                (0, 0)
+            }
+        } else {
+            if len > 48 {
+                seed ^= self.see1 ^ self.see2;
+            }
+            let mut rest = bytes;
+            while rest.len() > 16 {
+                seed = wymix(
+                    read_u64(&rest[..8]) ^ WY_SECRET[1],
+                    read_u64(&rest[8..16]) ^ seed,
+                );
+                rest = &rest[16..];
+            }
+            // the last 16 bytes of the input, even if part of them was mixed in already
+            let mut last = [0; 16];
+            let split = 16 - bytes.len().min(16);
+            last[..split].copy_from_slice(&self.mixed_tail[16 - split..]);
+            last[split..].copy_from_slice(&bytes[bytes.len() - (16 - split)..]);
+            (read_u64(&last[..8]), read_u64(&last[8..]))
+        };
+
+        let (a, b) = wymum(a ^ WY_SECRET[1], b ^ seed);
+        wymix(a ^ WY_SECRET[0] ^ len, b ^ WY_SECRET[1])
+    }
+}
+
+// SipHash-1-3, the same function as std's `DefaultHasher`
+// https://www.aumasson.jp/siphash/siphash.pdf
+pub struct SipHasher13 {
+    v: [u64; 4],
+    len: u64,
+    tail: Buffer<8>,
+}
+
+impl Default for SipHasher13 {
+    fn default() -> Self {
+        Self::with_keys(0, 0)
+    }
+}
+
+impl SipHasher13 {
+    pub fn with_keys(k0: u64, k1: u64) -> Self {
+        Self {
+            v: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            len: 0,
+            tail: Buffer::new(),
+        }
+    }
+}
+
+impl SeedableHasher for SipHasher13 {
+    fn with_seed(seed: u64) -> Self {
+        Self::with_keys(seed, 0)
+    }
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+// one compression round per 8-byte word
+fn sip_compress(v: &mut [u64; 4], m: u64) {
+    v[3] ^= m;
+    sip_round(v);
+    v[0] ^= m;
+}
+
+impl Hasher for SipHasher13 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        while !bytes.is_empty() {
+            bytes = self.tail.fill(bytes);
+            if let Some(word) = self.tail.take_full() {
+                sip_compress(&mut self.v, read_u64(&word));
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut v = self.v;
+        // the length goes into the top byte of the last word
+        sip_compress(&mut v, (self.len << 56) | read_tail(self.tail.as_slice()));
+
+        v[2] ^= 0xff;
+        for _ in 0..3 {
+            sip_round(&mut v);
+        }
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+// Input bytes that do not fill a block yet.
+struct Buffer<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Buffer<N> {
+    fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    // takes as many bytes as fit and returns the others
+    fn fill<'a>(&mut self, input: &'a [u8]) -> &'a [u8] {
+        let taken = input.len().min(N - self.len);
+        self.bytes[self.len..self.len + taken].copy_from_slice(&input[..taken]);
+        self.len += taken;
+        &input[taken..]
+    }
+
+    // empties the buffer if it holds a whole block
+    fn take_full(&mut self) -> Option<[u8; N]> {
+        if self.len < N {
+            return None;
+        }
+        self.len = 0;
+        Some(self.bytes)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap())
+}
+
+// little-endian value of up to eight bytes
+fn read_tail(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | byte as u64)
+}
+
+// Quality measures. Keys come from splitmix64 so that results are reproducible.
+
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Strict avalanche criterion over `samples` random `u64` keys.
+///
+/// Flipping any input bit should flip every output bit with probability 1/2.
+/// Returns the largest deviation from that over all pairs of input and output
+/// bits, so a good hash scores close to 0 and one that ignores a bit scores 0.5.
+pub fn avalanche_bias<S: BuildHasher>(state: &S, samples: usize) -> f64 {
+    let mut flips = [[0usize; 64]; 64];
+    let mut rng = 0;
+    for _ in 0..samples {
+        let key = splitmix64(&mut rng);
+        let hash = state.hash_one(key);
+        for (input, counts) in flips.iter_mut().enumerate() {
+            let diff = hash ^ state.hash_one(key ^ (1 << input));
+            for (output, count) in counts.iter_mut().enumerate() {
+                *count += (diff >> output & 1) as usize;
+            }
+        }
+    }
+    flips
+        .iter()
+        .flatten()
+        .map(|&count| (count as f64 / samples as f64 - 0.5).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Chi-squared statistic of how `keys` spread over `buckets` buckets, picked
+/// the way `HashTable` does it, as the hash modulo the bucket count.
+///
+/// The statistic is divided by its degrees of freedom, so uniform hashing
+/// scores about 1 and clustering scores higher.
+pub fn bucket_chi_squared<S, K, I>(state: &S, keys: I, buckets: usize) -> f64
+where
+    S: BuildHasher,
+    K: Hash,
+    I: IntoIterator<Item = K>,
+{
+    let mut counts = vec![0usize; buckets];
+    let mut total = 0;
+    for key in keys {
+        counts[(state.hash_one(key) % buckets as u64) as usize] += 1;
+        total += 1;
+    }
+    let expected = total as f64 / buckets as f64;
+    let chi_squared: f64 = counts
+        .iter()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum();
+    chi_squared / (buckets - 1) as f64
+}
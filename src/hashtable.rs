@@ -1,111 +1,214 @@
-use crate::prime::is_prime;
+use crate::prime::next_prime;
 
+pub use crate::hasher::HasherDJB2;
+
+use std::borrow::Borrow;
 use std::fmt::{self, Debug};
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::slice;
+use std::vec;
 
 // inspired by https://github.com/jamesroutley/write-a-hash-table
-// currently, it's a very simple hashmap with linear probing
+// currently, it's a very simple hashmap with open addressing, probing linearly by default
+
+pub(crate) const INITIAL_BASE_SIZE: usize = 53;
 
-const INITIAL_BASE_SIZE: usize = 53;
+// number of buckets to hold `items` items without going above 70% load
+pub(crate) fn buckets_for(items: usize) -> usize {
+    (items * 100 / 70 + 1).max(INITIAL_BASE_SIZE)
+}
 
-pub struct HashTable<K: Eq + Hash, V> {
-    buckets: Vec<Option<Entry<K, V>>>,
+/// Builds the hasher a `HashTable` uses when none is given.
+pub type DefaultState = BuildHasherDefault<HasherDJB2>;
+
+pub struct HashTable<K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    buckets: Vec<Option<Slot<K, V>>>,
     items: usize,
+    // removed items still marking their bucket, probes have to go past them
+    tombstones: usize,
+    hash_builder: S,
+    probe_strategy: ProbeStrategy,
+}
+
+/// Order in which `HashTable` visits the buckets after the one a key hashes to.
+///
+/// Every strategy reaches every bucket, so an insert always finds a free one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProbeStrategy {
+    /// `h, h + 1, h + 2, ...`
+    #[default]
+    Linear,
+    /// `h, h + 1, h - 1, h + 4, h - 4, ...`, the table size is a prime `p` with
+    /// `p % 4 == 3`, for which the squares and their negations cover all buckets.
+    Quadratic,
+    /// `h, h + 1, h + 3, h + 6, ...`, adding the triangular numbers, which
+    /// covers all buckets of a table whose size is a power of two.
+    Triangular,
+    /// `h, h + s, h + 2s, ...` with a step `s` from a second hash of the key,
+    /// the table size is prime so that every step covers all buckets.
+    DoubleHashing,
 }
 
 #[derive(Clone, Debug)]
-enum Entry<K: Eq + Hash, V> {
+enum Slot<K: Eq + Hash, V> {
     Item(K, V),
     Tombstone,
 }
 
 impl<K: Eq + Hash, V> Default for HashTable<K, V> {
     fn default() -> Self {
-        let mut m = Self {
-            buckets: Vec::new(),
-            items: 0,
-        };
-        m.resize(INITIAL_BASE_SIZE);
-        m
+        Self::with_capacity(0)
     }
 }
 
 impl<K: Eq + Hash, V> HashTable<K, V> {
+    // room for `capacity` items before the table has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultState::default())
+    }
+
+    pub fn with_probe_strategy(probe_strategy: ProbeStrategy) -> Self {
+        Self::with_hasher_and_probe_strategy(DefaultState::default(), probe_strategy)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> HashTable<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self::build(capacity, hash_builder, ProbeStrategy::default())
+    }
+
+    pub fn with_hasher_and_probe_strategy(hash_builder: S, probe_strategy: ProbeStrategy) -> Self {
+        Self::build(0, hash_builder, probe_strategy)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn probe_strategy(&self) -> ProbeStrategy {
+        self.probe_strategy
+    }
+
     pub fn insert(&mut self, key: K, value: V) {
-        // if load is above 0.7, resize
-        if self.load() > 70 {
-            self.resize_up();
-        }
-        let mut idx = self.find_index(&key);
-        loop {
-            let cur_item = &mut self.buckets[idx];
-            match cur_item {
-                Some(Entry::Tombstone) | None => {
-                    cur_item.replace(Entry::Item(key, value));
-                    self.items += 1;
-                    break;
-                }
-                Some(Entry::Item(cur_key, ..)) if *cur_key == key => {
-                    cur_item.replace(Entry::Item(key, value));
-                    break;
-                }
-                _ => idx = self.next_index(idx),
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
             }
         }
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
-        // after map is cleared, find_index might try to calculate remainder with zero
-        if self.buckets.is_empty() {
-            return None;
-        }
-
-        let mut idx = self.find_index(key);
-        let start_idx = idx;
-        loop {
-            match &self.buckets[idx] {
-                Some(Entry::Item(cur_key, cur_value)) if cur_key == key => return Some(cur_value),
-                None => return None,
-                _ => {
-                    idx = self.next_index(idx);
-                }
-            }
-            if idx == start_idx {
-                return None;
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let mut probed = self.probe(&key);
+        // only a new key takes up room: if items and tombstones are about to
+        // go above 0.7, resize, or just drop the tombstones if that leaves
+        // enough room
+        if probed.is_err() && self.items + self.tombstones >= self.capacity() {
+            if self.items > self.capacity() / 2 {
+                self.resize_up();
+            } else {
+                self.compact();
             }
+            probed = self.probe(&key);
+        }
+        match probed {
+            Ok(idx) => Entry::Occupied(OccupiedEntry { table: self, idx }),
+            Err(idx) => Entry::Vacant(VacantEntry {
+                table: self,
+                key,
+                idx,
+            }),
         }
     }
 
-    pub fn contains_key(&self, key: &K) -> bool {
-        self.get(key).is_some()
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
     }
 
-    pub fn remove(&mut self, key: &K) {
-        // if load is below 0.1, resize
-        if self.load() < 10 {
-            self.resize_down();
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match self.probe(key) {
+            Ok(idx) => match &mut self.buckets[idx] {
+                Some(Slot::Item(_, value)) => Some(value),
+                _ => unreachable!(),
+            },
+            Err(_) => None,
         }
+    }
 
-        if self.buckets.is_empty() {
-            return;
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match self.probe(key) {
+            Ok(idx) => match &self.buckets[idx] {
+                Some(Slot::Item(key, value)) => Some((key, value)),
+                _ => unreachable!(),
+            },
+            Err(_) => None,
         }
-        let mut idx = self.find_index(&key);
-        let start_idx = idx;
-        loop {
-            let cur_item = &mut self.buckets[idx];
-            match cur_item {
-                Some(Entry::Item(cur_key, ..)) if cur_key == key => {
-                    cur_item.replace(Entry::Tombstone);
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.probe(key).ok()?;
+        self.items -= 1;
+        self.tombstones += 1;
+        let removed = match self.buckets[idx].replace(Slot::Tombstone) {
+            Some(Slot::Item(key, value)) => (key, value),
+            _ => unreachable!(),
+        };
+        self.shrink_if_sparse();
+        Some(removed)
+    }
+
+    // keeps only the items for which `f` returns true
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        for bucket in &mut self.buckets {
+            if let Some(Slot::Item(key, value)) = bucket {
+                if !f(key, value) {
+                    *bucket = Some(Slot::Tombstone);
                     self.items -= 1;
-                    return;
+                    self.tombstones += 1;
                 }
-                None => return,                  // nothing found
-                _ => idx = self.next_index(idx), // continue, because key does not match or tombstone
-            }
-            if idx == start_idx {
-                return;
             }
         }
+        self.shrink_if_sparse();
     }
 
     pub fn len(&self) -> usize {
@@ -116,46 +219,183 @@ impl<K: Eq + Hash, V> HashTable<K, V> {
         self.items == 0
     }
 
+    // removes all items but keeps the buckets
     pub fn clear(&mut self) -> Vec<(K, V)> {
-        let items: Vec<_> = self
-            .buckets
-            .drain(..)
-            .filter_map(|entry| {
-                if let Some(Entry::Item(k, v)) = entry {
-                    Some((k, v))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        self.items = 0;
-        items
+        self.drain().collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.buckets.iter(),
+            remaining: self.items,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            buckets: self.buckets.iter_mut(),
+            remaining: self.items,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    // removes all items, even those the iterator was not advanced to, but keeps the buckets
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        Drain {
+            table: self,
+            idx: 0,
+        }
     }
 
-    pub(crate) fn capacity(&self) -> usize {
+    // removes and yields the items for which `pred` returns true, the items the
+    // iterator was not advanced to stay in the table
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, K, V, S, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        DrainFilter {
+            unvisited: self.items,
+            table: self,
+            idx: 0,
+            pred,
+        }
+    }
+
+    // number of items the table holds before it has to grow, the same count
+    // `with_capacity` and `reserve` take
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 70 / 100
+    }
+
+    // the table grows once 70% of them are used
+    pub fn bucket_count(&self) -> usize {
         self.buckets.len()
     }
 
-    fn find_index(&self, key: &K) -> usize {
-        let mut hasher = HasherDJB2::default();
-        key.hash(&mut hasher);
-        (hasher.finish() % self.buckets.len() as u64) as usize
+    // makes room for `additional` more items without growing on the way, it
+    // never shrinks the table
+    pub fn reserve(&mut self, additional: usize) {
+        if self.items + additional > self.capacity() {
+            self.resize(buckets_for(self.items + additional));
+        }
+    }
+
+    // shrinks the buckets as much as the load factor allows, dropping tombstones too
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(buckets_for(self.items));
+    }
+
+    // rehashes the items into the same buckets, dropping all tombstones
+    pub fn compact(&mut self) {
+        self.resize(self.bucket_count());
+    }
+
+    // buckets left behind by removed items, until the next resize or compaction
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    fn build(capacity: usize, hash_builder: S, probe_strategy: ProbeStrategy) -> Self {
+        let mut m = Self {
+            buckets: Vec::new(),
+            items: 0,
+            tombstones: 0,
+            hash_builder,
+            probe_strategy,
+        };
+        m.resize(buckets_for(capacity));
+        m
+    }
+
+    // Index of the key's bucket, or of the bucket where it should be inserted:
+    // the first tombstone on the way, or the empty bucket that ends the probe.
+    // The key can still come after a tombstone, so only an empty bucket stops the search.
+    fn probe<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut tombstone = None;
+        for idx in self.probe_sequence(key) {
+            match &self.buckets[idx] {
+                Some(Slot::Item(cur_key, _)) if cur_key.borrow() == key => return Ok(idx),
+                Some(Slot::Item(..)) => {}
+                Some(Slot::Tombstone) => {
+                    tombstone.get_or_insert(idx);
+                }
+                None => return Err(tombstone.unwrap_or(idx)),
+            }
+        }
+        // the load factor keeps some bucket free, so there is a tombstone
+        Err(tombstone.unwrap())
+    }
+
+    fn probe_sequence<Q>(&self, key: &Q) -> ProbeSequence
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let len = self.buckets.len();
+        let step = match self.probe_strategy {
+            ProbeStrategy::DoubleHashing => {
+                // the second hash function is the first one with a salt in front of the key
+                let mut hasher = self.hash_builder.build_hasher();
+                hasher.write_u64(0x9e37_79b9_7f4a_7c15);
+                key.hash(&mut hasher);
+                1 + (hasher.finish() % (len - 1) as u64) as usize
+            }
+            _ => 1,
+        };
+        let home = self.find_index(key);
+        ProbeSequence {
+            strategy: self.probe_strategy,
+            home,
+            idx: home,
+            step,
+            probed: 0,
+            len,
+        }
     }
 
-    fn next_index(&self, idx: usize) -> usize {
-        (idx + 1) % self.buckets.len()
+    fn find_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
     }
 
-    fn resize(&mut self, mut size: usize) {
+    fn resize(&mut self, size: usize) {
         if size < INITIAL_BASE_SIZE {
             return;
         }
         let items = self.clear();
 
-        // buckets size had better be a prime
-        while !is_prime(size) {
-            size += 1;
-        }
+        // buckets size had better be a prime, except for triangular probing
+        let size = match self.probe_strategy {
+            ProbeStrategy::Linear | ProbeStrategy::DoubleHashing => next_prime(size),
+            ProbeStrategy::Quadratic => {
+                let mut size = next_prime(size);
+                while size % 4 != 3 {
+                    size = next_prime(size + 1);
+                }
+                size
+            }
+            ProbeStrategy::Triangular => size.next_power_of_two(),
+        };
         self.buckets.resize_with(size, || None);
 
         for (k, v) in items {
@@ -164,86 +404,535 @@ impl<K: Eq + Hash, V> HashTable<K, V> {
     }
 
     fn load(&self) -> usize {
-        self.items * 100 / self.capacity()
+        self.items * 100 / self.bucket_count()
     }
 
     fn resize_up(&mut self) {
-        self.resize(self.capacity() * 2)
+        self.resize(self.bucket_count() * 2)
     }
 
     fn resize_down(&mut self) {
-        self.resize(self.capacity() / 2)
+        self.resize(self.bucket_count() / 2)
+    }
+
+    // Every removal ends here: if load is below 0.1, resize. That also drops
+    // the tombstones the removals left.
+    fn shrink_if_sparse(&mut self) {
+        if self.load() < 10 {
+            self.resize_down();
+        }
     }
 }
 
-impl<K, V> Debug for HashTable<K, V>
+impl<K, V, S> Debug for HashTable<K, V, S>
 where
+    S: BuildHasher,
     K: Eq + Hash + Debug,
     V: Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut dm = f.debug_map();
-        self.buckets
-            .iter()
-            .filter_map(|entry| match entry {
-                Some(Entry::Item(k, v)) => Some((k, v)),
-                _ => None,
-            })
-            .for_each(|(k, v)| {
-                dm.entry(k, v);
-            });
-        dm.finish()
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
-impl<K: Eq + Hash + Clone, V: Clone> Clone for HashTable<K, V> {
+impl<K: Eq + Hash + Clone, V: Clone, S: BuildHasher + Clone> Clone for HashTable<K, V, S> {
     fn clone(&self) -> Self {
         Self {
             buckets: self.buckets.clone(),
             items: self.items,
+            tombstones: self.tombstones,
+            hash_builder: self.hash_builder.clone(),
+            probe_strategy: self.probe_strategy,
         }
     }
 }
 
-impl<K, V> PartialEq for HashTable<K, V>
+impl<K, V, S> PartialEq for HashTable<K, V, S>
 where
+    S: BuildHasher,
     K: Eq + Hash,
     V: PartialEq,
 {
-    fn eq(&self, other: &HashTable<K, V>) -> bool {
+    fn eq(&self, other: &HashTable<K, V, S>) -> bool {
         if self.len() != other.len() {
             return false;
         }
 
-        self.buckets.iter().all(|entry| match entry {
-            Some(Entry::Item(key, value)) => other.get(key).map_or(false, |v| *value == *v),
-            _ => true,
-        })
+        self.iter()
+            .all(|(key, value)| other.get(key) == Some(value))
     }
 }
 
-// D. J. Bernstein hash function
-// http://cr.yp.to/cdb/cdb.txt
-pub struct HasherDJB2 {
-    hash: u64,
+// The buckets a key may be in, in the order given by the probe strategy. Every
+// bucket comes up exactly once.
+struct ProbeSequence {
+    strategy: ProbeStrategy,
+    home: usize,
+    idx: usize,
+    step: usize,
+    probed: usize,
+    len: usize,
 }
 
-impl Default for HasherDJB2 {
-    fn default() -> Self {
-        Self { hash: 5381u64 }
+impl Iterator for ProbeSequence {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.probed == self.len {
+            return None;
+        }
+        let n = self.probed;
+        self.probed += 1;
+        if n > 0 {
+            self.idx = match self.strategy {
+                ProbeStrategy::Linear => (self.idx + 1) % self.len,
+                ProbeStrategy::Quadratic => {
+                    let i = (n as u128).div_ceil(2);
+                    let square = (i * i % self.len as u128) as usize;
+                    if n % 2 == 1 {
+                        (self.home + square) % self.len
+                    } else {
+                        (self.home + self.len - square) % self.len
+                    }
+                }
+                ProbeStrategy::Triangular => (self.idx + n) & (self.len - 1),
+                ProbeStrategy::DoubleHashing => (self.idx + self.step) % self.len,
+            };
+        }
+        Some(self.idx)
+    }
+}
+
+// panics if the key is not in the table
+impl<K, Q, V, S> Index<&Q> for HashTable<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for HashTable<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashTable<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::with_hasher(S::default());
+        m.extend(iter);
+        m
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> IntoIterator for HashTable<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            buckets: self.buckets.into_iter(),
+            remaining: self.items,
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a HashTable<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a mut HashTable<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// The iterators walk the buckets and count down the items still ahead, which
+// makes `size_hint` exact and lets them stop before the trailing empty buckets.
+
+pub struct Iter<'a, K: Eq + Hash, V> {
+    buckets: slice::Iter<'a, Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K: Eq + Hash, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.buckets.find_map(|bucket| match bucket {
+            Some(Slot::Item(key, value)) => Some((key, value)),
+            _ => None,
+        });
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-impl Hasher for HasherDJB2 {
-    fn write(&mut self, bytes: &[u8]) {
-        for byte in bytes {
-            self.hash = (self.hash << 5)
-                .wrapping_add(self.hash)
-                .wrapping_add(*byte as u64);
+impl<K: Eq + Hash, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K: Eq + Hash, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            buckets: self.buckets.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+pub struct IterMut<'a, K: Eq + Hash, V> {
+    buckets: slice::IterMut<'a, Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K: Eq + Hash, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
+        let item = self.buckets.find_map(|bucket| match bucket {
+            Some(Slot::Item(key, value)) => Some((&*key, value)),
+            _ => None,
+        });
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct Keys<'a, K: Eq + Hash, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: Eq + Hash, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K: Eq + Hash, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K: Eq + Hash, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
     }
 
-    fn finish(&self) -> u64 {
-        self.hash
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K: Eq + Hash, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K: Eq + Hash, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+pub struct IntoIter<K: Eq + Hash, V> {
+    buckets: vec::IntoIter<Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<K: Eq + Hash, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.buckets.find_map(|bucket| match bucket {
+            Some(Slot::Item(key, value)) => Some((key, value)),
+            _ => None,
+        });
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for IntoIter<K, V> {}
+
+// Empties the buckets one by one and keeps the table's counts in step, so a
+// leaked `Drain` leaves a table that is only partly drained, not a broken one.
+pub struct Drain<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut HashTable<K, V, S>,
+    idx: usize,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Iterator for Drain<'_, K, V, S> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        // tombstones are cleared on the way as well
+        while self.table.items + self.table.tombstones > 0 {
+            let bucket = self.table.buckets[self.idx].take();
+            self.idx += 1;
+            match bucket {
+                Some(Slot::Item(key, value)) => {
+                    self.table.items -= 1;
+                    return Some((key, value));
+                }
+                Some(Slot::Tombstone) => self.table.tombstones -= 1,
+                None => {}
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.table.items, Some(self.table.items))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ExactSizeIterator for Drain<'_, K, V, S> {}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Drop for Drain<'_, K, V, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+pub struct DrainFilter<'a, K: Eq + Hash, V, S: BuildHasher, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    table: &'a mut HashTable<K, V, S>,
+    idx: usize,
+    // items not passed to `pred` yet
+    unvisited: usize,
+    pred: F,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher, F> Iterator for DrainFilter<'_, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.unvisited > 0 {
+            let bucket = &mut self.table.buckets[self.idx];
+            self.idx += 1;
+            if let Some(Slot::Item(key, value)) = bucket {
+                self.unvisited -= 1;
+                if (self.pred)(key, value) {
+                    self.table.items -= 1;
+                    self.table.tombstones += 1;
+                    match bucket.replace(Slot::Tombstone) {
+                        Some(Slot::Item(key, value)) => return Some((key, value)),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.unvisited))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher, F> Drop for DrainFilter<'_, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    fn drop(&mut self) {
+        self.table.shrink_if_sparse();
+    }
+}
+
+/// A view into a single bucket of a `HashTable`, returned by `HashTable::entry`.
+pub enum Entry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut HashTable<K, V, S>,
+    idx: usize,
+}
+
+pub struct VacantEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut HashTable<K, V, S>,
+    key: K,
+    idx: usize,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(&entry.key);
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        self.item().0
+    }
+
+    pub fn get(&self) -> &V {
+        self.item().1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        match &mut self.table.buckets[self.idx] {
+            Some(Slot::Item(_, value)) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        match &mut self.table.buckets[self.idx] {
+            Some(Slot::Item(_, value)) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    // returns the old value, the key is left as it is
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.table.items -= 1;
+        self.table.tombstones += 1;
+        let removed = match self.table.buckets[self.idx].replace(Slot::Tombstone) {
+            Some(Slot::Item(key, value)) => (key, value),
+            _ => unreachable!(),
+        };
+        self.table.shrink_if_sparse();
+        removed
+    }
+
+    fn item(&self) -> (&K, &V) {
+        match &self.table.buckets[self.idx] {
+            Some(Slot::Item(key, value)) => (key, value),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.table.items += 1;
+        let bucket = &mut self.table.buckets[self.idx];
+        if let Some(Slot::Tombstone) = bucket {
+            self.table.tombstones -= 1;
+        }
+        *bucket = Some(Slot::Item(self.key, value));
+        match bucket {
+            Some(Slot::Item(_, value)) => value,
+            _ => unreachable!(),
+        }
     }
 }
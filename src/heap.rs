@@ -1,48 +1,76 @@
 use std::cmp::Ordering;
 use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut, Range};
+use std::slice;
+use std::vec;
 
-pub struct BinaryHeap<'a, T, F>
+/// Implicit heap with `D` children per node, ordered by a comparator it owns.
+///
+/// The element for which `cmp` returns `Ordering::Less` against every other
+/// element is popped first, so `|a, b| a.cmp(b)` gives a min-heap and
+/// `|a, b| b.cmp(a)` a max-heap. Wider nodes make the tree shallower, which
+/// speeds up `push` and keeps sibling comparisons within a cache line.
+pub struct DaryHeap<T, F, const D: usize>
 where
-    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
     values: Vec<T>,
-    cmp: &'a F,
+    cmp: F,
 }
 
-impl<'a, T, F> BinaryHeap<'a, T, F>
+/// The classic heap with two children per node.
+pub type BinaryHeap<T, F> = DaryHeap<T, F, 2>;
+
+/// Heap with a type-erased comparator, handy when the heap has to be named
+/// in a struct field or returned from a function.
+pub type BoxedBinaryHeap<T> = BinaryHeap<T, Box<dyn Fn(&T, &T) -> Ordering + Send + Sync>>;
+
+impl<T: Ord, const D: usize> DaryHeap<T, fn(&T, &T) -> Ordering, D> {
+    /// Heap that pops the smallest element first.
+    pub fn min() -> Self {
+        Self::new(|a, b| a.cmp(b))
+    }
+
+    /// Heap that pops the largest element first.
+    pub fn max() -> Self {
+        Self::new(|a, b| b.cmp(a))
+    }
+}
+
+impl<T, F, const D: usize> DaryHeap<T, F, D>
 where
-    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
-    pub fn new(cmp: &'a F) -> Self {
+    // evaluated by every constructor, so that `D == 0` fails to compile
+    const VALID: () = assert!(D > 0, "a DaryHeap needs at least one child per node");
+
+    pub fn new(cmp: F) -> Self {
+        let () = Self::VALID;
         Self {
             values: Vec::new(),
             cmp,
         }
     }
 
-    pub fn from_vec(values: Vec<T>, cmp: &'a F) -> Self {
-        let mut heap = Self { values, cmp };
-        if let Some(i) = Self::parent(heap.values.len()) {
-            for idx in (0..=i).rev() {
-                heap.heapify(idx);
-            }
+    pub fn with_capacity(capacity: usize, cmp: F) -> Self {
+        let () = Self::VALID;
+        Self {
+            values: Vec::with_capacity(capacity),
+            cmp,
         }
+    }
+
+    pub fn from_vec(values: Vec<T>, cmp: F) -> Self {
+        let () = Self::VALID;
+        let mut heap = Self { values, cmp };
+        heap.rebuild();
         heap
     }
 
     pub fn push(&mut self, value: T) {
         self.values.push(value);
-
-        let mut idx = self.values.len() - 1;
-        while let Some(parent) = Self::parent(idx) {
-            if (*self.cmp)(&self.values[idx], &self.values[parent]) == Ordering::Greater {
-                break;
-            }
-            self.values.swap(idx, parent);
-            idx = parent;
-        }
+        self.sift_up(self.values.len() - 1);
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -55,6 +83,19 @@ where
         })
     }
 
+    pub fn peek(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    // the root is sifted down again when the returned guard is dropped
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, F, D>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(PeekMut { heap: self })
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.values.len()
     }
@@ -63,96 +104,405 @@ where
         self.values.len() == 0
     }
 
-    fn heapify(&mut self, mut idx: usize) {
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    pub fn comparator(&self) -> &F {
+        &self.cmp
+    }
+
+    // checks the heap property, which only breaks with an inconsistent comparator
+    pub fn validate(&self) -> Result<(), Violation> {
+        for child in 1..self.values.len() {
+            let parent = Self::parent(child).unwrap();
+            if (self.cmp)(&self.values[child], &self.values[parent]) == Ordering::Less {
+                return Err(Violation { parent, child });
+            }
+        }
+        Ok(())
+    }
+
+    // renders the implicit tree in Graphviz DOT format
+    pub fn to_dot(&self) -> String
+    where
+        T: Debug,
+    {
+        let mut dot = String::from("digraph heap {\n");
+        for (idx, value) in self.values.iter().enumerate() {
+            let label = format!("{:?}", value)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            dot.push_str(&format!("    {} [label=\"{}\"];\n", idx, label));
+            if let Some(parent) = Self::parent(idx) {
+                dot.push_str(&format!("    {} -> {};\n", parent, idx));
+            }
+        }
+        dot.push('}');
+        dot
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    // the items are returned in the order they are stored, not in heap order
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    // the items are returned in the order they would be popped
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        Self::pop_to_back(&mut self.values, &self.cmp);
+        self.values.reverse();
+        self.values
+    }
+
+    // yields the items in the order they would be popped
+    pub fn into_iter_sorted(self) -> IntoIter<T, F, D> {
+        IntoIter { heap: self }
+    }
+
+    // yields the items in the order they are stored, not in heap order
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    // the items are returned in the order they are stored, not in heap order
+    pub fn drain(&mut self) -> vec::Drain<'_, T> {
+        self.values.drain(..)
+    }
+
+    // pops the items one by one, whatever is left is dropped together with the iterator
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, F, D> {
+        DrainSorted { heap: self }
+    }
+
+    pub fn retain<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let len = self.values.len();
+        self.values.retain(|value| predicate(value));
+        if self.values.len() != len {
+            self.rebuild();
+        }
+    }
+
+    // moves all items of `other` into `self`, ordering them by `self`'s comparator
+    pub fn append(&mut self, other: &mut Self) {
+        let start = self.values.len();
+        self.values.append(&mut other.values);
+        self.rebuild_tail(start);
+    }
+
+    // sift-up each new item when there are few of them, rebuild from scratch otherwise
+    fn rebuild_tail(&mut self, start: usize) {
+        let len = self.values.len();
+        let added = len - start;
+        if added == 0 {
+            return;
+        }
+        let log_len = (usize::BITS - len.leading_zeros()) as usize;
+        if added * log_len < 2 * len {
+            for idx in start..len {
+                self.sift_up(idx);
+            }
+        } else {
+            self.rebuild();
+        }
+    }
+
+    fn rebuild(&mut self) {
+        Self::rebuild_slice(&mut self.values, &self.cmp)
+    }
+
+    fn rebuild_slice(values: &mut [T], cmp: &F) {
+        if let Some(i) = Self::parent(values.len()) {
+            for idx in (0..=i).rev() {
+                Self::heapify_slice(values, cmp, idx);
+            }
+        }
+    }
+
+    fn sift_up(&mut self, mut idx: usize) {
+        while let Some(parent) = Self::parent(idx) {
+            if (self.cmp)(&self.values[idx], &self.values[parent]) == Ordering::Greater {
+                break;
+            }
+            self.values.swap(idx, parent);
+            idx = parent;
+        }
+    }
+
+    fn heapify(&mut self, idx: usize) {
+        Self::heapify_slice(&mut self.values, &self.cmp, idx)
+    }
+
+    // sorts a valid heap so that the first item to pop ends up at the back
+    fn pop_to_back(values: &mut [T], cmp: &F) {
+        for end in (1..values.len()).rev() {
+            values.swap(0, end);
+            Self::heapify_slice(&mut values[..end], cmp, 0);
+        }
+    }
+
+    fn heapify_slice(values: &mut [T], cmp: &F, mut idx: usize) {
+        let end = values.len();
         let mut min_or_max;
         loop {
             min_or_max = idx;
-            if let Some(left) = self.left_child(idx) {
-                if (*self.cmp)(&self.values[left], &self.values[min_or_max]) == Ordering::Less {
-                    min_or_max = left;
-                }
-            }
-            if let Some(right) = self.right_child(idx) {
-                if (*self.cmp)(&self.values[right], &self.values[min_or_max]) == Ordering::Less {
-                    min_or_max = right;
+            for child in Self::children(idx, end) {
+                if cmp(&values[child], &values[min_or_max]) == Ordering::Less {
+                    min_or_max = child;
                 }
             }
             if min_or_max == idx {
                 break;
             }
-            self.values.swap(min_or_max, idx);
+            values.swap(min_or_max, idx);
             idx = min_or_max;
         }
     }
 
-    fn left_child(&self, idx: usize) -> Option<usize> {
-        let left_child = 2 * idx + 1;
-        if left_child >= self.values.len() {
-            return None;
-        }
-        Some(left_child)
-    }
-
-    fn right_child(&self, idx: usize) -> Option<usize> {
-        let right_child = 2 * idx + 2;
-        if right_child >= self.values.len() {
-            return None;
-        }
-        Some(right_child)
+    fn children(idx: usize, end: usize) -> Range<usize> {
+        let first_child = (D * idx + 1).min(end);
+        first_child..(first_child + D).min(end)
     }
 
     fn parent(idx: usize) -> Option<usize> {
         if idx == 0 {
             return None;
         }
-        if idx % 2 == 0 {
-            Some((idx - 2) / 2)
-        } else {
-            Some((idx - 1) / 2)
+        Some((idx - 1) / D)
+    }
+}
+
+impl<T, F, const D: usize> Clone for DaryHeap<T, F, D>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            cmp: self.cmp.clone(),
         }
     }
 }
 
-impl<'a, T, F> Debug for BinaryHeap<'a, T, F>
+impl<T, F, const D: usize> Extend<T> for DaryHeap<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.values.len();
+        self.values.extend(iter);
+        self.rebuild_tail(start);
+    }
+}
+
+// collecting produces a min-heap, use `from_vec` for any other order
+impl<T: Ord, const D: usize> FromIterator<T> for DaryHeap<T, fn(&T, &T) -> Ordering, D> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect(), |a, b| a.cmp(b))
+    }
+}
+
+impl<T, F, const D: usize> Debug for DaryHeap<T, F, D>
 where
-    T: PartialOrd + Debug,
+    T: Debug,
     F: Fn(&T, &T) -> Ordering,
 {
+    // draws the implicit tree, one item per line
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.values.is_empty() {
+            return write!(f, "(empty)");
+        }
+        write!(f, "{:?}", self.values[0])?;
+
+        // (item, indentation of its line, whether it is its parent's last child)
+        let mut stack = Vec::new();
+        let push_children = |stack: &mut Vec<_>, idx: usize, prefix: String| {
+            let children = Self::children(idx, self.values.len());
+            let last = children.end;
+            for child in children.rev() {
+                stack.push((child, prefix.clone(), child + 1 == last));
+            }
+        };
+        push_children(&mut stack, 0, String::new());
+        while let Some((idx, prefix, is_last)) = stack.pop() {
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            write!(f, "\n{}{}{:?}", prefix, branch, self.values[idx])?;
+            push_children(&mut stack, idx, prefix + indent);
+        }
+        Ok(())
+    }
+}
+
+/// Parent/child pair that breaks the heap property: the child ranks before
+/// its parent according to the heap's comparator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub parent: usize,
+    pub child: usize,
+}
+
+impl fmt::Display for Violation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.values)
+        write!(
+            f,
+            "item at index {} ranks before its parent at index {}",
+            self.child, self.parent
+        )
     }
 }
 
-impl<'a, T, F> IntoIterator for &'a mut BinaryHeap<'a, T, F>
+impl std::error::Error for Violation {}
+
+impl<T, F, const D: usize> IntoIterator for DaryHeap<T, F, D>
 where
-    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
     type Item = T;
-    type IntoIter = Iter<'a, T, F>;
+    type IntoIter = IntoIter<T, F, D>;
 
     fn into_iter(self) -> Self::IntoIter {
-        Iter { inner: self }
+        IntoIter { heap: self }
+    }
+}
+
+impl<'a, T, F, const D: usize> IntoIterator for &'a DaryHeap<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T, F, const D: usize>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    heap: DaryHeap<T, F, D>,
+}
+
+impl<T, F, const D: usize> Iterator for IntoIter<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.heap.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
     }
 }
 
-pub struct Iter<'a, T, F>
+impl<T, F, const D: usize> ExactSizeIterator for IntoIter<T, F, D> where F: Fn(&T, &T) -> Ordering {}
+
+pub struct DrainSorted<'a, T, F, const D: usize>
 where
-    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
-    inner: &'a mut BinaryHeap<'a, T, F>,
+    heap: &'a mut DaryHeap<T, F, D>,
 }
 
-impl<'a, T, F> Iterator for Iter<'a, T, F>
+impl<'a, T, F, const D: usize> Iterator for DrainSorted<'a, T, F, D>
 where
-    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
     type Item = T;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.pop()
+        self.heap.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<'a, T, F, const D: usize> ExactSizeIterator for DrainSorted<'a, T, F, D> where
+    F: Fn(&T, &T) -> Ordering
+{
+}
+
+impl<'a, T, F, const D: usize> Drop for DrainSorted<'a, T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+pub struct PeekMut<'a, T, F, const D: usize>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    heap: &'a mut DaryHeap<T, F, D>,
+}
+
+impl<'a, T, F, const D: usize> PeekMut<'a, T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // removes the peeked item, the heap is already valid again when the guard is dropped
+    pub fn pop(this: Self) -> T {
+        this.heap.pop().unwrap()
     }
 }
+
+impl<'a, T, F, const D: usize> Deref for PeekMut<'a, T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.values[0]
+    }
+}
+
+impl<'a, T, F, const D: usize> DerefMut for PeekMut<'a, T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.heap.values[0]
+    }
+}
+
+impl<'a, T, F, const D: usize> Drop for PeekMut<'a, T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn drop(&mut self) {
+        self.heap.heapify(0);
+    }
+}
+
+// Sorts `values` in place in the order a heap ordered by `cmp` would pop them.
+pub fn sort_by<T, F>(values: &mut [T], cmp: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // with the comparator reversed, the first item to pop is the last one to extract
+    let rev = |a: &T, b: &T| cmp(b, a);
+    BinaryHeap::rebuild_slice(values, &rev);
+    BinaryHeap::pop_to_back(values, &rev);
+}
@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+/// Reference to an item pushed into a `PairingHeap` or a `FibonacciHeap`,
+/// used to decrease its key later on.
+///
+/// The handle does not keep the item alive: once the item is popped or the
+/// heap is dropped, `decrease_key` panics for it. So it does when the handle
+/// comes from another heap, unless that heap was melded into this one.
+pub struct Handle<N> {
+    pub(crate) node: Weak<RefCell<N>>,
+    pub(crate) heap: HeapId,
+}
+
+impl<N> Clone for Handle<N> {
+    fn clone(&self) -> Self {
+        Self {
+            node: self.node.clone(),
+            heap: self.heap.clone(),
+        }
+    }
+}
+
+// Identity of a heap, shared with its handles. A heap melded into another one
+// points its id to the other heap's, so its handles are accepted there.
+#[derive(Clone)]
+pub(crate) struct HeapId(Rc<RefCell<Option<HeapId>>>);
+
+impl HeapId {
+    pub(crate) fn new() -> Self {
+        Self(Rc::new(RefCell::new(None)))
+    }
+
+    // `self` has to belong to a heap that is still around, so it is its own root
+    pub(crate) fn contains(&self, handle: &HeapId) -> bool {
+        Rc::ptr_eq(&self.0, &handle.root().0)
+    }
+
+    pub(crate) fn absorb(&self, other: &HeapId) {
+        *other.0.borrow_mut() = Some(self.clone());
+    }
+
+    // follows the melds to the heap the id belongs to now, shortening the way
+    // for the next lookup
+    fn root(&self) -> HeapId {
+        let mut path = Vec::new();
+        let mut id = self.clone();
+        loop {
+            let next = id.0.borrow().clone();
+            match next {
+                Some(next) => path.push(mem::replace(&mut id, next)),
+                None => break,
+            }
+        }
+        for step in path {
+            *step.0.borrow_mut() = Some(id.clone());
+        }
+        id
+    }
+}
@@ -2,14 +2,29 @@
 #![allow(clippy::needless_range_loop)]
 #![feature(cell_leak)]
 
+pub mod chained;
+pub mod concurrent_heap;
+pub mod cuckoo;
+pub mod fibonacci_heap;
+pub mod flat;
 pub mod graph;
 pub mod graph_arena;
 pub mod graph_ref;
+pub mod hasher;
 pub mod hashtable;
 pub mod heap;
+pub mod heap_handle;
+pub mod median;
+pub mod minmax_heap;
+pub mod pairing_heap;
+pub mod persistent_heap;
 pub mod prime;
+pub mod radix_heap;
+pub mod robin_hood;
+pub mod scheduler;
 pub mod search;
 pub mod sort;
+pub mod top_k;
 
 #[cfg(test)]
 mod tests;
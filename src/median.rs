@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::heap::BinaryHeap;
+
+// the order comes from the constructor, `BinaryHeap::max` or `BinaryHeap::min`
+type FnHeap<T> = BinaryHeap<T, fn(&T, &T) -> Ordering>;
+
+/// Median of all items pushed so far.
+///
+/// The smaller half of the items is kept in a max-heap and the larger half in
+/// a min-heap, so the median is always on top of one of them.
+pub struct RunningMedian<T: Ord> {
+    // largest on top
+    lower: FnHeap<T>,
+    // smallest on top
+    upper: FnHeap<T>,
+}
+
+impl<T: Ord> Default for RunningMedian<T> {
+    fn default() -> Self {
+        Self {
+            lower: BinaryHeap::max(),
+            upper: BinaryHeap::min(),
+        }
+    }
+}
+
+impl<T: Ord> RunningMedian<T> {
+    pub fn push(&mut self, value: T) {
+        match self.lower.peek() {
+            Some(top) if value > *top => self.upper.push(value),
+            _ => self.lower.push(value),
+        }
+
+        // the lower half holds the extra item for odd counts
+        if self.lower.len() > self.upper.len() + 1 {
+            self.upper.push(self.lower.pop().unwrap());
+        } else if self.upper.len() > self.lower.len() {
+            self.lower.push(self.upper.pop().unwrap());
+        }
+    }
+
+    // the lower of the two middle items for even counts
+    pub fn median(&self) -> Option<&T> {
+        self.lower.peek()
+    }
+
+    // both middle items, the same one twice for odd counts
+    pub fn middle(&self) -> Option<(&T, &T)> {
+        let low = self.lower.peek()?;
+        if self.lower.len() > self.upper.len() {
+            Some((low, low))
+        } else {
+            self.upper.peek().map(|high| (low, high))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+}
+
+/// Quantile of the last `window` pushed items.
+///
+/// Items that leave the window are not searched for in the heaps. Every item
+/// carries its sequence number instead, and expired items are dropped once they
+/// surface on top of a heap, or in bulk when they outnumber the live ones.
+/// The quantile is the nearest-rank one: the item at position
+/// `ceil(quantile * len)` in sorted order, counting from one.
+pub struct SlidingQuantile<T: Ord> {
+    // largest on top
+    lower: FnHeap<(T, usize)>,
+    // smallest on top
+    upper: FnHeap<(T, usize)>,
+    // for every item in the window, oldest first, whether it is in `lower`
+    in_lower: VecDeque<bool>,
+    lower_len: usize,
+    upper_len: usize,
+    window: usize,
+    quantile: f64,
+    pushed: usize,
+}
+
+impl<T: Ord> SlidingQuantile<T> {
+    pub fn new(window: usize, quantile: f64) -> Self {
+        assert!(window > 0, "window must not be empty");
+        assert!(
+            (0.0..=1.0).contains(&quantile),
+            "quantile must be between 0 and 1"
+        );
+        Self {
+            lower: BinaryHeap::max(),
+            upper: BinaryHeap::min(),
+            in_lower: VecDeque::with_capacity(window),
+            lower_len: 0,
+            upper_len: 0,
+            window,
+            quantile,
+            pushed: 0,
+        }
+    }
+
+    pub fn median(window: usize) -> Self {
+        Self::new(window, 0.5)
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.in_lower.len() == self.window {
+            if self.in_lower.pop_front().unwrap() {
+                self.lower_len -= 1;
+            } else {
+                self.upper_len -= 1;
+            }
+            self.prune();
+        }
+
+        let seq = self.pushed;
+        self.pushed += 1;
+        // the lower half may be empty after the oldest item left it
+        let to_lower = match (self.lower.peek(), self.upper.peek()) {
+            (Some((top, _)), _) => value <= *top,
+            (None, Some((bottom, _))) => value <= *bottom,
+            (None, None) => true,
+        };
+        if to_lower {
+            self.lower.push((value, seq));
+            self.lower_len += 1;
+        } else {
+            self.upper.push((value, seq));
+            self.upper_len += 1;
+        }
+        self.in_lower.push_back(to_lower);
+        self.rebalance();
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.lower.peek().map(|(value, _)| value)
+    }
+
+    // number of items in the window
+    pub fn len(&self) -> usize {
+        self.in_lower.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.in_lower.is_empty()
+    }
+
+    // moves items between the halves until the lower one ends with the quantile
+    fn rebalance(&mut self) {
+        let len = self.len();
+        let target = ((self.quantile * len as f64).ceil() as usize).clamp(1, len);
+        while self.lower_len > target {
+            let (value, seq) = self.lower.pop().unwrap();
+            self.mark(seq, false);
+            self.upper.push((value, seq));
+            self.prune();
+        }
+        while self.lower_len < target {
+            let (value, seq) = self.upper.pop().unwrap();
+            self.mark(seq, true);
+            self.lower.push((value, seq));
+            self.prune();
+        }
+    }
+
+    fn mark(&mut self, seq: usize, in_lower: bool) {
+        let first = self.pushed - self.in_lower.len();
+        self.in_lower[seq - first] = in_lower;
+        if in_lower {
+            self.lower_len += 1;
+            self.upper_len -= 1;
+        } else {
+            self.lower_len -= 1;
+            self.upper_len += 1;
+        }
+    }
+
+    // drops expired items from the tops, and from everywhere once they dominate
+    fn prune(&mut self) {
+        let first = self.pushed - self.in_lower.len();
+        let expired = |item: &(T, usize)| item.1 < first;
+
+        if self.lower.len() > 2 * self.lower_len + 16 {
+            self.lower.retain(|item| !expired(item));
+        }
+        if self.upper.len() > 2 * self.upper_len + 16 {
+            self.upper.retain(|item| !expired(item));
+        }
+        while self.lower.peek().is_some_and(expired) {
+            self.lower.pop();
+        }
+        while self.upper.peek().is_some_and(expired) {
+            self.upper.pop();
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+
+/// Double-ended priority queue: both ends are available in O(1) and removed
+/// in O(log n).
+///
+/// The tree levels alternate between "min" levels, where a node ranks before
+/// all of its descendants according to `cmp`, and "max" levels, where it ranks
+/// after them. The root is the minimum, one of its children the maximum.
+pub struct MinMaxHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    values: Vec<T>,
+    cmp: F,
+}
+
+impl<T: Ord> Default for MinMaxHeap<T, fn(&T, &T) -> Ordering> {
+    fn default() -> Self {
+        Self::new(|a, b| a.cmp(b))
+    }
+}
+
+impl<T, F> MinMaxHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    pub fn new(cmp: F) -> Self {
+        Self {
+            values: Vec::new(),
+            cmp,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize, cmp: F) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+            cmp,
+        }
+    }
+
+    pub fn from_vec(values: Vec<T>, cmp: F) -> Self {
+        let mut heap = Self { values, cmp };
+        for idx in (0..heap.values.len() / 2).rev() {
+            heap.trickle_down(idx);
+        }
+        heap
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+        self.push_up(self.values.len() - 1);
+    }
+
+    pub fn peek_min(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    pub fn peek_max(&self) -> Option<&T> {
+        self.max_index().map(|idx| &self.values[idx])
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.max_index().and_then(|idx| self.remove(idx))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    // the items are returned in the order they are stored, not in heap order
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx >= self.values.len() {
+            return None;
+        }
+        let value = self.values.swap_remove(idx);
+        if idx < self.values.len() {
+            self.trickle_down(idx);
+        }
+        Some(value)
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.values.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => {
+                if (self.cmp)(&self.values[1], &self.values[2]) == Ordering::Less {
+                    Some(2)
+                } else {
+                    Some(1)
+                }
+            }
+        }
+    }
+
+    // `Less` on min levels, `Greater` on max levels
+    fn direction(idx: usize) -> Ordering {
+        let level = usize::BITS - 1 - (idx + 1).leading_zeros();
+        if level.is_multiple_of(2) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    fn ranks_before(&self, a: usize, b: usize, direction: Ordering) -> bool {
+        (self.cmp)(&self.values[a], &self.values[b]) == direction
+    }
+
+    fn push_up(&mut self, idx: usize) {
+        let parent = match Self::parent(idx) {
+            Some(parent) => parent,
+            None => return,
+        };
+        let direction = Self::direction(idx);
+        // an item that belongs to the other kind of level moves there first
+        if self.ranks_before(parent, idx, direction) {
+            self.values.swap(idx, parent);
+            self.push_up_levels(parent, direction.reverse());
+        } else {
+            self.push_up_levels(idx, direction);
+        }
+    }
+
+    // bubbles the item up along the levels of the same kind
+    fn push_up_levels(&mut self, mut idx: usize, direction: Ordering) {
+        while let Some(grandparent) = Self::parent(idx).and_then(Self::parent) {
+            if !self.ranks_before(idx, grandparent, direction) {
+                break;
+            }
+            self.values.swap(idx, grandparent);
+            idx = grandparent;
+        }
+    }
+
+    fn trickle_down(&mut self, mut idx: usize) {
+        let direction = Self::direction(idx);
+        loop {
+            // the child or grandchild that ranks first for this level
+            let first_child = 2 * idx + 1;
+            let first_grandchild = 4 * idx + 3;
+            let end = (first_grandchild + 4).min(self.values.len());
+            let mut best = first_child;
+            if best >= end {
+                return;
+            }
+            for candidate in (first_child + 1..first_child + 2).chain(first_grandchild..end) {
+                if candidate < end && self.ranks_before(candidate, best, direction) {
+                    best = candidate;
+                }
+            }
+
+            if !self.ranks_before(best, idx, direction) {
+                return;
+            }
+            self.values.swap(best, idx);
+            if best < first_grandchild {
+                return;
+            }
+            // the item moved down two levels, it may now belong to the level in between
+            let parent = Self::parent(best).unwrap();
+            if self.ranks_before(parent, best, direction) {
+                self.values.swap(best, parent);
+            }
+            idx = best;
+        }
+    }
+
+    fn parent(idx: usize) -> Option<usize> {
+        if idx == 0 {
+            return None;
+        }
+        Some((idx - 1) / 2)
+    }
+}
+
+impl<T, F> Debug for MinMaxHeap<T, F>
+where
+    T: Debug,
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.values)
+    }
+}
@@ -0,0 +1,233 @@
+use crate::heap_handle::{self, HeapId};
+
+use std::cell::{Ref, RefCell};
+use std::cmp::Ordering;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+use node::Node;
+
+// Every node owns its leftmost child and its right sibling, and keeps a weak
+// link to its left sibling or, for the leftmost child, to its parent.
+
+type NodeRef<T> = Rc<RefCell<Node<T>>>;
+
+// public only so that `Handle` can name it, the module keeps it out of reach
+mod node {
+    use super::NodeRef;
+    use std::cell::RefCell;
+    use std::rc::Weak;
+
+    pub struct Node<T> {
+        pub(super) value: T,
+        pub(super) child: Option<NodeRef<T>>,
+        pub(super) sibling: Option<NodeRef<T>>,
+        pub(super) prev: Weak<RefCell<Node<T>>>,
+    }
+}
+
+pub type Handle<T> = heap_handle::Handle<Node<T>>;
+
+/// Pairing heap with O(1) `push` and `meld` and amortized O(log n) `pop`.
+///
+/// Items are ordered by `cmp` the same way as in `heap::BinaryHeap`.
+pub struct PairingHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    root: Option<NodeRef<T>>,
+    len: usize,
+    id: HeapId,
+    cmp: F,
+}
+
+impl<T: Ord> PairingHeap<T, fn(&T, &T) -> Ordering> {
+    pub fn min() -> Self {
+        Self::new(|a, b| a.cmp(b))
+    }
+
+    pub fn max() -> Self {
+        Self::new(|a, b| b.cmp(a))
+    }
+}
+
+impl<T, F> PairingHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    pub fn new(cmp: F) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            id: HeapId::new(),
+            cmp,
+        }
+    }
+
+    pub fn push(&mut self, value: T) -> Handle<T> {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            child: None,
+            sibling: None,
+            prev: Weak::new(),
+        }));
+        let handle = Handle {
+            node: Rc::downgrade(&node),
+            heap: self.id.clone(),
+        };
+        self.root = Some(match self.root.take() {
+            Some(root) => self.link(root, node),
+            None => node,
+        });
+        self.len += 1;
+        handle
+    }
+
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        self.root
+            .as_ref()
+            .map(|root| Ref::map(root.borrow(), |node| &node.value))
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let children = root.borrow_mut().child.take();
+        self.root = self.merge_pairs(children);
+        self.len -= 1;
+
+        // handles are weak, so the heap holds the only strong reference
+        match Rc::try_unwrap(root) {
+            Ok(node) => Some(node.into_inner().value),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    // moves all items of `other` into `self`, ordering them by `self`'s comparator
+    pub fn meld(&mut self, mut other: Self) {
+        self.len += mem::replace(&mut other.len, 0);
+        self.id.absorb(&other.id);
+        self.root = match (self.root.take(), other.root.take()) {
+            (Some(a), Some(b)) => Some(self.link(a, b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    // `handle` has to come from this heap or from a heap melded into it
+    pub fn decrease_key(&mut self, handle: &Handle<T>, value: T) {
+        assert!(
+            self.id.contains(&handle.heap),
+            "handle is from another heap"
+        );
+        let node = handle
+            .node
+            .upgrade()
+            .expect("item is no longer in the heap");
+        {
+            let mut node = node.borrow_mut();
+            assert!(
+                (self.cmp)(&value, &node.value) != Ordering::Greater,
+                "new key ranks after the current one"
+            );
+            node.value = value;
+        }
+
+        if self
+            .root
+            .as_ref()
+            .is_some_and(|root| Rc::ptr_eq(root, &node))
+        {
+            return;
+        }
+        Self::cut(&node);
+        let root = self.root.take().unwrap();
+        self.root = Some(self.link(root, node));
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // makes the root that ranks after the other one the leftmost child of the other
+    fn link(&self, a: NodeRef<T>, b: NodeRef<T>) -> NodeRef<T> {
+        let a_first = (self.cmp)(&a.borrow().value, &b.borrow().value) != Ordering::Greater;
+        let (parent, child) = if a_first { (a, b) } else { (b, a) };
+        {
+            let mut parent_node = parent.borrow_mut();
+            let mut child_node = child.borrow_mut();
+            child_node.prev = Rc::downgrade(&parent);
+            if let Some(first) = parent_node.child.take() {
+                first.borrow_mut().prev = Rc::downgrade(&child);
+                child_node.sibling = Some(first);
+            }
+        }
+        parent.borrow_mut().child = Some(child);
+        parent
+    }
+
+    // the standard two-pass merge: link siblings pairwise from the left,
+    // then fold the results from the right
+    fn merge_pairs(&self, mut first: Option<NodeRef<T>>) -> Option<NodeRef<T>> {
+        let mut pairs = Vec::new();
+        while let Some(a) = first {
+            match Self::detach(&a) {
+                Some(b) => {
+                    first = Self::detach(&b);
+                    pairs.push(self.link(a, b));
+                }
+                None => {
+                    first = None;
+                    pairs.push(a);
+                }
+            }
+        }
+        pairs
+            .into_iter()
+            .rev()
+            .reduce(|merged, tree| self.link(tree, merged))
+    }
+
+    // unlinks the node from its left neighbour, returning its right sibling
+    fn detach(node: &NodeRef<T>) -> Option<NodeRef<T>> {
+        let mut node = node.borrow_mut();
+        node.prev = Weak::new();
+        node.sibling.take()
+    }
+
+    // removes a non-root node together with its subtree from the tree
+    fn cut(node: &NodeRef<T>) {
+        let (prev, sibling) = {
+            let mut node = node.borrow_mut();
+            let prev = mem::replace(&mut node.prev, Weak::new());
+            (prev.upgrade().unwrap(), node.sibling.take())
+        };
+        if let Some(sibling) = &sibling {
+            sibling.borrow_mut().prev = Rc::downgrade(&prev);
+        }
+
+        let mut prev = prev.borrow_mut();
+        if prev.child.as_ref().is_some_and(|c| Rc::ptr_eq(c, node)) {
+            prev.child = sibling;
+        } else {
+            prev.sibling = sibling;
+        }
+    }
+}
+
+impl<T, F> Drop for PairingHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // sibling chains can be as long as the heap, so drop the nodes without recursion
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut node = node.borrow_mut();
+            stack.extend(node.child.take());
+            stack.extend(node.sibling.take());
+        }
+    }
+}
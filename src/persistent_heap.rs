@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// Persistent (immutable) heaps. Every operation returns a new heap and leaves
+// the old one intact: only the nodes on the merge path are copied, the rest of
+// the tree is shared through `Rc`, so keeping old versions around is cheap.
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    value: Rc<T>,
+    // length of the right spine, only maintained by the leftist heap
+    rank: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> Drop for Node<T> {
+    // a left spine can be as long as the heap, so drop the nodes without recursion
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self
+            .left
+            .take()
+            .into_iter()
+            .chain(self.right.take())
+            .collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+fn rank<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.rank)
+}
+
+fn leaf<T>(value: T) -> Link<T> {
+    Some(Rc::new(Node {
+        value: Rc::new(value),
+        rank: 1,
+        left: None,
+        right: None,
+    }))
+}
+
+// Walks down the right spines of both heaps, then rebuilds the path bottom-up,
+// letting `join` combine each copied node's value and left child with the
+// already merged rest.
+fn merge<T, F>(
+    a: &Link<T>,
+    b: &Link<T>,
+    cmp: &F,
+    join: fn(Rc<T>, Link<T>, Link<T>) -> Link<T>,
+) -> Link<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut path = Vec::new();
+    let (mut a, mut b) = (a.clone(), b.clone());
+    let mut merged = loop {
+        match (a, b) {
+            (Some(x), Some(y)) => {
+                let (top, other) = if cmp(&x.value, &y.value) != Ordering::Greater {
+                    (x, y)
+                } else {
+                    (y, x)
+                };
+                a = top.right.clone();
+                b = Some(other);
+                path.push(top);
+            }
+            (rest, None) | (None, rest) => break rest,
+        }
+    };
+    for node in path.into_iter().rev() {
+        merged = join(Rc::clone(&node.value), node.left.clone(), merged);
+    }
+    merged
+}
+
+// keeps the child with the shorter right spine on the right
+fn leftist_join<T>(value: Rc<T>, left: Link<T>, merged: Link<T>) -> Link<T> {
+    let (left, right) = if rank(&left) >= rank(&merged) {
+        (left, merged)
+    } else {
+        (merged, left)
+    };
+    Some(Rc::new(Node {
+        value,
+        rank: rank(&right) + 1,
+        left,
+        right,
+    }))
+}
+
+// unconditionally swaps the children
+fn skew_join<T>(value: Rc<T>, left: Link<T>, merged: Link<T>) -> Link<T> {
+    Some(Rc::new(Node {
+        value,
+        rank: 0,
+        left: merged,
+        right: left,
+    }))
+}
+
+macro_rules! persistent_heap {
+    ($(#[$attr:meta])* $name:ident, $join:ident) => {
+        $(#[$attr])*
+        pub struct $name<T, F>
+        where
+            F: Fn(&T, &T) -> Ordering,
+        {
+            root: Link<T>,
+            len: usize,
+            cmp: Rc<F>,
+        }
+
+        impl<T: Ord> $name<T, fn(&T, &T) -> Ordering> {
+            pub fn min() -> Self {
+                Self::new(|a, b| a.cmp(b))
+            }
+
+            pub fn max() -> Self {
+                Self::new(|a, b| b.cmp(a))
+            }
+        }
+
+        impl<T, F> $name<T, F>
+        where
+            F: Fn(&T, &T) -> Ordering,
+        {
+            pub fn new(cmp: F) -> Self {
+                Self {
+                    root: None,
+                    len: 0,
+                    cmp: Rc::new(cmp),
+                }
+            }
+
+            pub fn push(&self, value: T) -> Self {
+                let root = merge(&self.root, &leaf(value), &*self.cmp, $join);
+                self.with_root(root, self.len + 1)
+            }
+
+            pub fn peek(&self) -> Option<&T> {
+                self.root.as_ref().map(|node| &*node.value)
+            }
+
+            // returns the top item together with the heap that is left without it
+            pub fn pop(&self) -> Option<(&T, Self)> {
+                self.root.as_ref().map(|node| {
+                    let rest = merge(&node.left, &node.right, &*self.cmp, $join);
+                    (&*node.value, self.with_root(rest, self.len - 1))
+                })
+            }
+
+            // orders the items of both heaps by `self`'s comparator
+            pub fn merge(&self, other: &Self) -> Self {
+                let root = merge(&self.root, &other.root, &*self.cmp, $join);
+                self.with_root(root, self.len + other.len)
+            }
+
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            fn with_root(&self, root: Link<T>, len: usize) -> Self {
+                Self {
+                    root,
+                    len,
+                    cmp: Rc::clone(&self.cmp),
+                }
+            }
+        }
+
+        // cloning only copies a pointer to the shared tree
+        impl<T, F> Clone for $name<T, F>
+        where
+            F: Fn(&T, &T) -> Ordering,
+        {
+            fn clone(&self) -> Self {
+                self.with_root(self.root.clone(), self.len)
+            }
+        }
+    };
+}
+
+persistent_heap!(
+    /// Persistent leftist heap, O(log n) `push`, `pop` and `merge` in the worst case.
+    LeftistHeap,
+    leftist_join
+);
+
+persistent_heap!(
+    /// Persistent skew heap, a self-adjusting leftist heap without ranks. Its
+    /// O(log n) bounds are amortized, so they do not hold when old versions of
+    /// the heap are reused heavily; prefer `LeftistHeap` for that.
+    SkewHeap,
+    skew_join
+);
@@ -5,20 +5,28 @@ pub fn is_prime(num: usize) -> bool {
     if num < 4 {
         return true;
     }
-    if num % 2 == 0 {
+    if num.is_multiple_of(2) {
         return false;
     }
     for i in (3..)
         .step_by(2)
         .take_while(|i| *i <= (num as f64).sqrt().floor() as usize)
     {
-        if num % i == 0 {
+        if num.is_multiple_of(i) {
             return false;
         }
     }
     true
 }
 
+// the smallest prime not below `num`
+pub fn next_prime(mut num: usize) -> usize {
+    while !is_prime(num) {
+        num += 1;
+    }
+    num
+}
+
 #[test]
 fn test_is_prime() {
     vec![
@@ -84,3 +92,11 @@ fn test_is_prime() {
     .iter()
     .for_each(|&num| assert!(is_prime(num)))
 }
+
+#[test]
+fn test_next_prime() {
+    assert_eq!(next_prime(0), 2);
+    assert_eq!(next_prime(53), 53);
+    assert_eq!(next_prime(106), 107);
+    assert_eq!(next_prime(9932), 9941);
+}
@@ -0,0 +1,70 @@
+use std::mem;
+
+const BUCKETS: usize = u64::BITS as usize + 1;
+
+/// Monotone priority queue for `u64` keys.
+///
+/// Keys pushed must not be smaller than the last popped key, which is always
+/// the case for Dijkstra's algorithm with non-negative weights. An item lives
+/// in the bucket given by the highest bit in which its key differs from the
+/// last popped one, so it is moved at most 64 times over its lifetime and no
+/// key comparisons are needed besides finding a bucket's minimum.
+pub struct RadixHeap<T> {
+    buckets: Vec<Vec<(u64, T)>>,
+    last: u64,
+    len: usize,
+}
+
+impl<T> Default for RadixHeap<T> {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| Vec::new()).collect(),
+            last: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<T> RadixHeap<T> {
+    pub fn push(&mut self, key: u64, value: T) {
+        assert!(
+            key >= self.last,
+            "key {} is smaller than the last popped key {}",
+            key,
+            self.last
+        );
+        self.buckets[Self::bucket(key, self.last)].push((key, value));
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<(u64, T)> {
+        if self.buckets[0].is_empty() {
+            let idx = (1..BUCKETS).find(|&idx| !self.buckets[idx].is_empty())?;
+            // the new minimum splits its bucket over the lower ones
+            let bucket = mem::take(&mut self.buckets[idx]);
+            self.last = bucket.iter().map(|&(key, _)| key).min().unwrap();
+            for (key, value) in bucket {
+                self.buckets[Self::bucket(key, self.last)].push((key, value));
+            }
+        }
+        self.len -= 1;
+        self.buckets[0].pop()
+    }
+
+    // the smallest key that can still be pushed
+    pub fn last_key(&self) -> u64 {
+        self.last
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket(key: u64, last: u64) -> usize {
+        (u64::BITS - (key ^ last).leading_zeros()) as usize
+    }
+}
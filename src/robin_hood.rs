@@ -0,0 +1,793 @@
+use crate::hashtable::{buckets_for, DefaultState, INITIAL_BASE_SIZE};
+use crate::prime::next_prime;
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Index;
+use std::slice;
+use std::vec;
+
+/// Open-addressing hash table with Robin Hood hashing.
+///
+/// Like `HashTable` it probes linearly, but every bucket remembers how far its
+/// item is from the bucket it hashes to. On insert, an item that is further
+/// from home than the one in its way takes that bucket and the other item
+/// moves on, which keeps probe lengths short and even. On removal the items
+/// after the hole shift back by one, so no tombstones are needed and lookups
+/// can stop as soon as they meet an item closer to home than they are.
+pub struct RobinHoodHashTable<K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    buckets: Vec<Option<Bucket<K, V>>>,
+    items: usize,
+    hash_builder: S,
+}
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    // how many buckets the item is past the one it hashes to
+    distance: usize,
+    key: K,
+    value: V,
+}
+
+impl<K: Eq + Hash, V> Default for RobinHoodHashTable<K, V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<K: Eq + Hash, V> RobinHoodHashTable<K, V> {
+    // room for `capacity` items before the table has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultState::default())
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> RobinHoodHashTable<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut m = Self {
+            buckets: Vec::new(),
+            items: 0,
+            hash_builder,
+        };
+        m.resize(buckets_for(capacity));
+        m
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        if let Some(idx) = self.find(&key) {
+            return Entry::Occupied(OccupiedEntry { table: self, idx });
+        }
+        // only a new key takes up room: if load is about to go above 0.7, resize
+        if self.items >= self.capacity() {
+            self.resize(self.bucket_count() * 2);
+        }
+        Entry::Vacant(VacantEntry { table: self, key })
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(key)?;
+        self.buckets[idx].as_ref().map(|bucket| &bucket.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(key)?;
+        self.buckets[idx].as_mut().map(|bucket| &mut bucket.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(key)?;
+        self.buckets[idx]
+            .as_ref()
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let idx = self.find(key)?;
+        let removed = self.take(idx);
+        self.shrink_if_sparse();
+        Some(removed)
+    }
+
+    // keeps only the items for which `f` returns true
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        self.drain_filter(|key, value| !f(key, value))
+            .for_each(drop);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    // removes all items but keeps the buckets
+    pub fn clear(&mut self) -> Vec<(K, V)> {
+        self.items = 0;
+        self.buckets
+            .iter_mut()
+            .filter_map(Option::take)
+            .map(|bucket| (bucket.key, bucket.value))
+            .collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.buckets.iter(),
+            remaining: self.items,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            buckets: self.buckets.iter_mut(),
+            remaining: self.items,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    // removes all items, even those the iterator was not advanced to, but keeps the buckets
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        Drain {
+            table: self,
+            idx: 0,
+        }
+    }
+
+    // removes and yields the items for which `pred` returns true, the items the
+    // iterator was not advanced to stay in the table
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, K, V, S, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        DrainFilter {
+            unvisited: self.items,
+            table: self,
+            idx: 0,
+            pred,
+        }
+    }
+
+    // number of items the table holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 70 / 100
+    }
+
+    // number of buckets, the table grows once 70% of them are used
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // makes room for `additional` more items without growing on the way, it
+    // never shrinks the table
+    pub fn reserve(&mut self, additional: usize) {
+        if self.items + additional > self.capacity() {
+            self.resize(buckets_for(self.items + additional));
+        }
+    }
+
+    // shrinks the buckets as much as the load factor allows
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(buckets_for(self.items));
+    }
+
+    // the longest distance of an item from its home bucket, which bounds every lookup
+    pub fn max_probe_distance(&self) -> usize {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|bucket| bucket.distance)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let mut idx = self.find_index(key);
+        let mut distance = 0;
+        loop {
+            match &self.buckets[idx] {
+                Some(bucket) if bucket.key.borrow() == key => return Some(idx),
+                // the key would have taken this bucket on insert
+                Some(bucket) if bucket.distance < distance => return None,
+                Some(_) => {}
+                None => return None,
+            }
+            idx = self.next_index(idx);
+            distance += 1;
+        }
+    }
+
+    fn find_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        (self.hash_builder.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    fn next_index(&self, idx: usize) -> usize {
+        (idx + 1) % self.buckets.len()
+    }
+
+    // Places a key that is not in the table yet and returns its bucket. The
+    // key stays in the first bucket it takes, only the items it displaces move on.
+    fn place(&mut self, key: K, value: V) -> usize {
+        let mut idx = self.find_index(&key);
+        let mut item = Bucket {
+            distance: 0,
+            key,
+            value,
+        };
+        let mut placed = None;
+        loop {
+            match &mut self.buckets[idx] {
+                None => {
+                    self.buckets[idx] = Some(item);
+                    self.items += 1;
+                    return placed.unwrap_or(idx);
+                }
+                // take from the rich: the resident is closer to home, so it moves on
+                Some(resident) if resident.distance < item.distance => {
+                    mem::swap(resident, &mut item);
+                    placed.get_or_insert(idx);
+                }
+                Some(_) => {}
+            }
+            idx = self.next_index(idx);
+            item.distance += 1;
+        }
+    }
+
+    // Removes the item in bucket `idx`. Backward shift: the following items
+    // are pulled one bucket closer to home, up to an empty bucket or an item
+    // that is home already, so no tombstones are needed.
+    fn take(&mut self, mut idx: usize) -> (K, V) {
+        let removed = self.buckets[idx].take().unwrap();
+        self.items -= 1;
+        loop {
+            let next = self.next_index(idx);
+            match self.buckets[next].take() {
+                Some(mut bucket) if bucket.distance > 0 => {
+                    bucket.distance -= 1;
+                    self.buckets[idx] = Some(bucket);
+                    idx = next;
+                }
+                bucket => {
+                    self.buckets[next] = bucket;
+                    break;
+                }
+            }
+        }
+        (removed.key, removed.value)
+    }
+
+    fn resize(&mut self, size: usize) {
+        if size < INITIAL_BASE_SIZE {
+            return;
+        }
+        let items = self.clear();
+        self.buckets.resize_with(next_prime(size), || None);
+        for (k, v) in items {
+            self.place(k, v);
+        }
+    }
+
+    fn load(&self) -> usize {
+        self.items * 100 / self.bucket_count()
+    }
+
+    // if load is below 0.1, resize
+    fn shrink_if_sparse(&mut self) {
+        if self.load() < 10 {
+            self.resize(self.bucket_count() / 2);
+        }
+    }
+}
+
+impl<K, V, S> Debug for RobinHoodHashTable<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> Clone for RobinHoodHashTable<K, V, S>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            buckets: self.buckets.clone(),
+            items: self.items,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K, V, S> PartialEq for RobinHoodHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &RobinHoodHashTable<K, V, S>) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        self.iter()
+            .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+// panics if the key is not in the table
+impl<K, Q, V, S> Index<&Q> for RobinHoodHashTable<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for RobinHoodHashTable<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for RobinHoodHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::with_hasher(S::default());
+        m.extend(iter);
+        m
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> IntoIterator for RobinHoodHashTable<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            buckets: self.buckets.into_iter(),
+            remaining: self.items,
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a RobinHoodHashTable<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a mut RobinHoodHashTable<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// The iterators walk the buckets and count down the items still ahead, which
+// makes `size_hint` exact and lets them stop before the trailing empty buckets.
+
+pub struct Iter<'a, K, V> {
+    buckets: slice::Iter<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let bucket = self.buckets.find_map(Option::as_ref)?;
+        self.remaining -= 1;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            buckets: self.buckets.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+pub struct IterMut<'a, K, V> {
+    buckets: slice::IterMut<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let bucket = self.buckets.find_map(Option::as_mut)?;
+        self.remaining -= 1;
+        Some((&bucket.key, &mut bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    buckets: vec::IntoIter<Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let bucket = self.buckets.find_map(|bucket| bucket)?;
+        self.remaining -= 1;
+        Some((bucket.key, bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+// Removes the items one by one with a backward shift, so a leaked `Drain`
+// leaves a table that is only partly drained, not a broken one.
+pub struct Drain<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut RobinHoodHashTable<K, V, S>,
+    idx: usize,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Iterator for Drain<'_, K, V, S> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.table.items > 0 {
+            if self.table.buckets[self.idx].is_some() {
+                return Some(self.table.take(self.idx));
+            }
+            self.idx = self.table.next_index(self.idx);
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.table.items, Some(self.table.items))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ExactSizeIterator for Drain<'_, K, V, S> {}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Drop for Drain<'_, K, V, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+pub struct DrainFilter<'a, K: Eq + Hash, V, S: BuildHasher, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    table: &'a mut RobinHoodHashTable<K, V, S>,
+    idx: usize,
+    // items not passed to `pred` yet, an item that shifts back past the end
+    // of the buckets is only met again once this has run out
+    unvisited: usize,
+    pred: F,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher, F> Iterator for DrainFilter<'_, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.unvisited > 0 {
+            if let Some(bucket) = &mut self.table.buckets[self.idx] {
+                self.unvisited -= 1;
+                if (self.pred)(&bucket.key, &mut bucket.value) {
+                    // `idx` stays, the next item shifts into this bucket
+                    return Some(self.table.take(self.idx));
+                }
+            }
+            self.idx = self.table.next_index(self.idx);
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.unvisited))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher, F> Drop for DrainFilter<'_, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    fn drop(&mut self) {
+        self.table.shrink_if_sparse();
+    }
+}
+
+/// A view into a single item of a `RobinHoodHashTable`, returned by `RobinHoodHashTable::entry`.
+pub enum Entry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut RobinHoodHashTable<K, V, S>,
+    idx: usize,
+}
+
+// the bucket is picked on insert, placing the key may move other items
+pub struct VacantEntry<'a, K: Eq + Hash, V, S: BuildHasher = DefaultState> {
+    table: &'a mut RobinHoodHashTable<K, V, S>,
+    key: K,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(&entry.key);
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.bucket().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.bucket().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.table.buckets[self.idx].as_mut().unwrap().value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.table.buckets[self.idx].as_mut().unwrap().value
+    }
+
+    // returns the old value, the key is left as it is
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let removed = self.table.take(self.idx);
+        self.table.shrink_if_sparse();
+        removed
+    }
+
+    fn bucket(&self) -> &Bucket<K, V> {
+        self.table.buckets[self.idx].as_ref().unwrap()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let idx = self.table.place(self.key, value);
+        &mut self.table.buckets[idx].as_mut().unwrap().value
+    }
+}
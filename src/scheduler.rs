@@ -0,0 +1,194 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::heap::BinaryHeap;
+
+/// Handle of a scheduled event, used to cancel or move it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EventId(u64);
+
+// Position of an event in the queue. The sequence number breaks ties between
+// equal timestamps in scheduling order, and tells whether the slot is stale.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Slot {
+    time: u64,
+    seq: u64,
+    id: u64,
+}
+
+// period and how to copy the value for the next occurrence
+type Repeat<T> = (u64, fn(&T) -> T);
+
+struct Event<T> {
+    value: T,
+    seq: u64,
+    repeat: Option<Repeat<T>>,
+}
+
+/// Discrete-event queue keyed by timestamp.
+///
+/// Events due at the same time fire in the order they were scheduled.
+/// Cancelled and rescheduled events are not searched for in the queue: their
+/// old slots are skipped once they reach the top.
+pub struct Scheduler<T> {
+    queue: BinaryHeap<Slot, fn(&Slot, &Slot) -> Ordering>,
+    events: HashMap<u64, Event<T>>,
+    now: u64,
+    next_id: u64,
+    next_seq: u64,
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self {
+            queue: BinaryHeap::min(),
+            events: HashMap::new(),
+            now: 0,
+            next_id: 0,
+            next_seq: 0,
+        }
+    }
+}
+
+impl<T> Scheduler<T> {
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn schedule_at(&mut self, time: u64, value: T) -> EventId {
+        self.insert(time, value, None)
+    }
+
+    pub fn schedule_in(&mut self, delay: u64, value: T) -> EventId {
+        self.insert(self.now + delay, value, None)
+    }
+
+    // fires at `start` and then every `period` until cancelled, each time with a copy of `value`
+    pub fn schedule_every(&mut self, start: u64, period: u64, value: T) -> EventId
+    where
+        T: Clone,
+    {
+        assert!(period > 0, "period must be positive");
+        self.insert(start, value, Some((period, T::clone)))
+    }
+
+    // returns the value of the event, `None` if it already fired or was cancelled
+    pub fn cancel(&mut self, id: EventId) -> Option<T> {
+        let event = self.events.remove(&id.0)?;
+        self.compact();
+        Some(event.value)
+    }
+
+    // moves a pending event, periodic events continue from the new time
+    pub fn reschedule(&mut self, id: EventId, time: u64) -> bool {
+        assert!(time >= self.now, "cannot schedule an event in the past");
+        let seq = self.next_seq;
+        match self.events.get_mut(&id.0) {
+            Some(event) => {
+                event.seq = seq;
+                self.next_seq += 1;
+                self.queue.push(Slot {
+                    time,
+                    seq,
+                    id: id.0,
+                });
+                self.compact();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_scheduled(&self, id: EventId) -> bool {
+        self.events.contains_key(&id.0)
+    }
+
+    // time of the earliest pending event
+    pub fn next_time(&mut self) -> Option<u64> {
+        self.skip_stale();
+        self.queue.peek().map(|slot| slot.time)
+    }
+
+    // fires the earliest pending event and moves the clock to its time
+    pub fn pop_next(&mut self) -> Option<(u64, EventId, T)> {
+        self.skip_stale();
+        let slot = self.queue.pop()?;
+        self.now = slot.time;
+        Some(self.fire(slot))
+    }
+
+    // fires every event due at or before `time`, in order, and moves the clock to `time`
+    pub fn advance_to(&mut self, time: u64) -> Vec<(u64, EventId, T)> {
+        assert!(time >= self.now, "cannot move the clock backwards");
+        let mut fired = Vec::new();
+        while self.next_time().is_some_and(|next| next <= time) {
+            fired.push(self.pop_next().unwrap());
+        }
+        self.now = time;
+        fired
+    }
+
+    // number of pending events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn insert(&mut self, time: u64, value: T, repeat: Option<Repeat<T>>) -> EventId {
+        assert!(time >= self.now, "cannot schedule an event in the past");
+        let (id, seq) = (self.next_id, self.next_seq);
+        self.next_id += 1;
+        self.next_seq += 1;
+        self.events.insert(id, Event { value, seq, repeat });
+        self.queue.push(Slot { time, seq, id });
+        EventId(id)
+    }
+
+    // the slot has to be current
+    fn fire(&mut self, slot: Slot) -> (u64, EventId, T) {
+        let id = EventId(slot.id);
+        let event = self.events.get_mut(&slot.id).unwrap();
+        let value = match event.repeat {
+            Some((period, copy)) => {
+                let seq = self.next_seq;
+                self.next_seq += 1;
+                event.seq = seq;
+                let time = slot.time + period;
+                self.queue.push(Slot {
+                    time,
+                    seq,
+                    id: slot.id,
+                });
+                copy(&event.value)
+            }
+            None => self.events.remove(&slot.id).unwrap().value,
+        };
+        (slot.time, id, value)
+    }
+
+    // drops all stale slots once they outnumber the pending events
+    fn compact(&mut self) {
+        if self.queue.len() > 2 * self.events.len() + 16 {
+            let events = &self.events;
+            self.queue.retain(|slot| {
+                events
+                    .get(&slot.id)
+                    .is_some_and(|event| event.seq == slot.seq)
+            });
+        }
+    }
+
+    fn skip_stale(&mut self) {
+        while let Some(slot) = self.queue.peek() {
+            match self.events.get(&slot.id) {
+                Some(event) if event.seq == slot.seq => return,
+                _ => {
+                    self.queue.pop();
+                }
+            }
+        }
+    }
+}
@@ -3,7 +3,7 @@ use rand::rngs::ThreadRng;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap as StdBinaryHeap;
 
-use crate::heap::BinaryHeap;
+use crate::heap;
 use crate::utils::Rev;
 
 // ============ quicksort ============
@@ -20,7 +20,7 @@ where
 {
     match scheme {
         PartitionScheme::Lomuto => quicksort_lomuto(arr, &mut rand::thread_rng(), cmp),
-        PartitionScheme::Hoare => quicksort_hoare(arr, &mut rand::thread_rng(), cmp),
+        PartitionScheme::Hoare => quicksort_hoare(arr, cmp),
     }
 }
 
@@ -49,7 +49,7 @@ where
     quicksort_lomuto(&mut arr[i + 1..], rng, cmp);
 }
 
-fn quicksort_hoare<T, F>(arr: &mut [T], rng: &mut ThreadRng, cmp: &F)
+fn quicksort_hoare<T, F>(arr: &mut [T], cmp: &F)
 where
     T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
@@ -78,8 +78,8 @@ where
         arr.swap(i, j);
     }
 
-    quicksort_hoare(&mut arr[..j], rng, cmp);
-    quicksort_hoare(&mut arr[j + 1..], rng, cmp);
+    quicksort_hoare(&mut arr[..j], cmp);
+    quicksort_hoare(&mut arr[j + 1..], cmp);
 }
 
 // ============ mergesort ============
@@ -130,13 +130,10 @@ where
 
 pub fn heapsort<T, F>(arr: &mut [T], cmp: &F)
 where
-    T: PartialOrd + Clone,
+    T: PartialOrd,
     F: Fn(&T, &T) -> Ordering,
 {
-    BinaryHeap::from_vec(arr.to_vec(), cmp)
-        .into_iter()
-        .enumerate()
-        .for_each(|(i, elem)| arr[i] = elem)
+    heap::sort_by(arr, cmp)
 }
 
 pub fn std_heapsort<T, F>(arr: &mut [T], cmp: &F)
@@ -1,8 +1,10 @@
 use crate::hashtable::HashTable;
+use crate::prime::is_prime;
 use rand::distributions::Standard;
 use rand::thread_rng;
 use rand::Rng;
 use std::cmp::Ordering;
+use std::hash::{BuildHasher, Hasher};
 
 fn test_suite(sort_fn: impl Fn(&mut [i32], &dyn Fn(&i32, &i32) -> Ordering) -> Vec<i32>) {
     // generate tests like hashtabletps://github.com/rust-lang/rust/blob/847ba835ce411d47364a93ddf0b4a5c0f27928a9/library/alloc/tests/slice.rs
@@ -105,7 +107,7 @@ fn heap_test() {
     use crate::heap::BinaryHeap;
     use rand::seq::SliceRandom;
 
-    let mut minheap = BinaryHeap::<u64, _>::new(&|a, b| a.cmp(b));
+    let mut minheap = BinaryHeap::<u64, _>::new(|a, b| a.cmp(b));
     let mut values = (0..1000).collect::<Vec<_>>();
     values.shuffle(&mut rand::thread_rng());
     for &elem in &values {
@@ -116,7 +118,7 @@ fn heap_test() {
     }
     assert!(values.windows(2).all(|w| w[0] <= w[1]));
 
-    let mut maxheap = BinaryHeap::<u64, _>::new(&|a, b| b.cmp(a));
+    let mut maxheap = BinaryHeap::<u64, _>::new(|a, b| b.cmp(a));
     values.shuffle(&mut rand::thread_rng());
     for &elem in &values {
         maxheap.push(elem);
@@ -127,6 +129,830 @@ fn heap_test() {
     assert!(values.windows(2).all(|w| w[0] >= w[1]));
 }
 
+#[test]
+fn heap_min_max() {
+    use crate::heap::BinaryHeap;
+
+    let mut minheap = BinaryHeap::min();
+    let mut maxheap = BinaryHeap::max();
+    for &elem in &[5, 1, 8, 3, 9, 2] {
+        minheap.push(elem);
+        maxheap.push(elem);
+    }
+    assert_eq!(minheap.into_iter().collect::<Vec<_>>(), [1, 2, 3, 5, 8, 9]);
+    assert_eq!(maxheap.into_iter().collect::<Vec<_>>(), [9, 8, 5, 3, 2, 1]);
+}
+
+#[test]
+fn heap_owned_comparator() {
+    use crate::heap::{BinaryHeap, BoxedBinaryHeap};
+
+    fn by_len(words: &[&'static str]) -> BoxedBinaryHeap<&'static str> {
+        let mut heap: BoxedBinaryHeap<_> =
+            BinaryHeap::new(Box::new(|a: &&str, b: &&str| a.len().cmp(&b.len())));
+        for &word in words {
+            heap.push(word);
+        }
+        heap
+    }
+
+    let heap = by_len(&["ccc", "a", "bb"]);
+    let handle = std::thread::spawn(move || heap.into_iter().collect::<Vec<_>>());
+    assert_eq!(handle.join().unwrap(), ["a", "bb", "ccc"]);
+}
+
+#[test]
+fn heap_peek() {
+    use crate::heap::{BinaryHeap, PeekMut};
+
+    let mut heap = BinaryHeap::min();
+    assert_eq!(heap.peek(), None);
+    assert!(heap.peek_mut().is_none());
+
+    heap.extend(vec![4, 7, 2, 9]);
+    assert_eq!(heap.peek(), Some(&2));
+
+    // the changed root has to be sifted down once the guard is dropped
+    *heap.peek_mut().unwrap() = 8;
+    assert_eq!(heap.peek(), Some(&4));
+
+    assert_eq!(PeekMut::pop(heap.peek_mut().unwrap()), 4);
+    assert_eq!(heap.into_sorted_vec(), [7, 8, 9]);
+}
+
+#[test]
+fn heap_collection_api() {
+    use crate::heap::BinaryHeap;
+    use rand::seq::SliceRandom;
+
+    let mut values = (0..100).collect::<Vec<u64>>();
+    values.shuffle(&mut rand::thread_rng());
+
+    let heap = BinaryHeap::from_vec(values.clone(), |a: &u64, b: &u64| b.cmp(a));
+    assert_eq!(heap.clone().into_vec().len(), 100);
+    assert_eq!(heap.into_sorted_vec(), (0..100).rev().collect::<Vec<_>>());
+
+    let mut heap = values.iter().copied().collect::<BinaryHeap<_, _>>();
+    heap.retain(|value| value % 3 == 0);
+    assert_eq!(heap.len(), 34);
+    assert_eq!(heap.pop(), Some(0));
+    assert_eq!(heap.pop(), Some(3));
+
+    let mut drained = heap.drain().collect::<Vec<_>>();
+    drained.sort();
+    assert!(heap.is_empty());
+    assert_eq!(drained, (6..100).step_by(3).collect::<Vec<_>>());
+}
+
+#[test]
+fn heap_append() {
+    use crate::heap::BinaryHeap;
+
+    // small and large batches take different paths
+    for &(left, right) in &[(100, 3), (3, 100), (0, 50), (50, 0)] {
+        let mut a = (0..left).map(|i| 2 * i).collect::<BinaryHeap<_, _>>();
+        let mut b = (0..right).map(|i| 2 * i + 1).collect::<BinaryHeap<_, _>>();
+        a.append(&mut b);
+
+        assert!(b.is_empty());
+        let sorted = a.into_sorted_vec();
+        assert_eq!(sorted.len(), left + right);
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+#[test]
+fn heap_iterators() {
+    use crate::heap::BinaryHeap;
+
+    let mut heap = BinaryHeap::from_vec(vec![3, 1, 4, 1, 5, 9, 2, 6], |a: &i32, b: &i32| b.cmp(a));
+
+    let mut stored = heap.iter().copied().collect::<Vec<_>>();
+    stored.sort();
+    assert_eq!(stored, [1, 1, 2, 3, 4, 5, 6, 9]);
+    assert_eq!((&heap).into_iter().count(), 8);
+
+    let iter = heap.clone().into_iter();
+    assert_eq!(iter.len(), 8);
+    assert_eq!(iter.collect::<Vec<_>>(), [9, 6, 5, 4, 3, 2, 1, 1]);
+    assert_eq!(
+        heap.clone().into_iter_sorted().take(3).collect::<Vec<_>>(),
+        [9, 6, 5]
+    );
+
+    // dropping the iterator early still empties the heap
+    assert_eq!(heap.drain_sorted().take(2).collect::<Vec<_>>(), [9, 6]);
+    assert!(heap.is_empty());
+
+    heap.push(7);
+    assert_eq!(heap.into_iter().collect::<Vec<_>>(), [7]);
+}
+
+#[test]
+fn dary_heap_test() {
+    use crate::heap::DaryHeap;
+    use rand::seq::SliceRandom;
+
+    fn check<const D: usize>(values: &[u64]) {
+        let mut heap = DaryHeap::<u64, _, D>::new(|a, b| a.cmp(b));
+        for &elem in values {
+            heap.push(elem);
+        }
+        let popped = heap.into_iter().collect::<Vec<_>>();
+        assert_eq!(popped.len(), values.len());
+        assert!(popped.windows(2).all(|w| w[0] <= w[1]));
+
+        let heap = DaryHeap::<u64, _, D>::from_vec(values.to_vec(), |a, b| b.cmp(a));
+        let sorted = heap.into_sorted_vec();
+        assert!(sorted.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    let mut values = (0..1000).collect::<Vec<_>>();
+    values.shuffle(&mut rand::thread_rng());
+    check::<1>(&values[..50]);
+    check::<3>(&values);
+    check::<4>(&values);
+    check::<8>(&values);
+    check::<16>(&values);
+}
+
+// the same checks for every heap with `push`, `pop`, `meld` and `decrease_key`
+macro_rules! meldable_heap_tests {
+    (
+        $module:ident::$heap:ident,
+        $basic:ident,
+        $meld:ident,
+        $decrease_key:ident,
+        $popped_handle:ident,
+        $foreign_handle:ident,
+        $melded_handles:ident
+    ) => {
+        #[test]
+        fn $basic() {
+            use crate::$module::$heap;
+            use rand::seq::SliceRandom;
+
+            let mut values = (0..1000).collect::<Vec<u64>>();
+            values.shuffle(&mut rand::thread_rng());
+
+            let mut heap = $heap::max();
+            for &value in &values {
+                heap.push(value);
+            }
+            assert_eq!(heap.len(), 1000);
+            assert_eq!(heap.peek().map(|top| *top), Some(999));
+            for expected in (500..1000).rev() {
+                assert_eq!(heap.pop(), Some(expected));
+            }
+            // dropping a large heap must not overflow the stack
+            let mut heap = $heap::min();
+            for value in 0..100_000 {
+                heap.push(value);
+            }
+            assert_eq!(heap.pop(), Some(0));
+        }
+
+        #[test]
+        fn $meld() {
+            use crate::$module::$heap;
+
+            let mut evens = $heap::min();
+            let mut odds = $heap::min();
+            for value in 0..50 {
+                evens.push(2 * value);
+                odds.push(2 * value + 1);
+            }
+            evens.meld(odds);
+            evens.meld($heap::min());
+            assert_eq!(evens.len(), 100);
+
+            let mut empty = $heap::min();
+            empty.meld(evens);
+            for expected in 0..100 {
+                assert_eq!(empty.pop(), Some(expected));
+            }
+            assert!(empty.is_empty());
+            assert_eq!(empty.pop(), None);
+        }
+
+        #[test]
+        fn $decrease_key() {
+            use crate::$module::$heap;
+            use rand::Rng;
+
+            let mut rng = rand::thread_rng();
+            let mut heap = $heap::new(|a: &(u64, usize), b: &(u64, usize)| a.cmp(b));
+            let mut keys = (0..500)
+                .map(|_| rng.gen_range(1000, 2000))
+                .collect::<Vec<u64>>();
+            let handles = keys
+                .iter()
+                .enumerate()
+                .map(|(i, &key)| heap.push((key, i)))
+                .collect::<Vec<_>>();
+
+            // interleave pops so that decreased items sit deep in the trees
+            let mut popped = vec![false; keys.len()];
+            for round in 0..2000 {
+                if round % 10 == 0 {
+                    let (key, i) = heap.pop().unwrap();
+                    assert_eq!(key, keys[i]);
+                    assert!(keys.iter().enumerate().all(|(j, &k)| popped[j] || k >= key));
+                    popped[i] = true;
+                    continue;
+                }
+                let i = rng.gen_range(0, keys.len());
+                if !popped[i] {
+                    keys[i] -= rng.gen_range(0, keys[i].min(20) + 1);
+                    heap.decrease_key(&handles[i], (keys[i], i));
+                }
+            }
+
+            let mut last = 0;
+            while let Some((key, i)) = heap.pop() {
+                assert_eq!(key, keys[i]);
+                assert!(key >= last);
+                last = key;
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "item is no longer in the heap")]
+        fn $popped_handle() {
+            use crate::$module::$heap;
+
+            let mut heap = $heap::min();
+            let handle = heap.push(5);
+            heap.pop();
+            heap.decrease_key(&handle, 1);
+        }
+
+        #[test]
+        fn $foreign_handle() {
+            use crate::$module::$heap;
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+
+            let mut heap = $heap::min();
+            let mut other = $heap::min();
+            let mut other_handles = Vec::new();
+            for value in 0..10 {
+                heap.push(value);
+                other_handles.push(other.push(10 + value));
+            }
+            other.pop();
+            // the other heap's top item, then one deep in its tree
+            for handle in [&other_handles[1], &other_handles[9]] {
+                let result = catch_unwind(AssertUnwindSafe(|| heap.decrease_key(handle, 0)));
+                let message = result.unwrap_err();
+                assert_eq!(
+                    message.downcast_ref::<&str>(),
+                    Some(&"handle is from another heap")
+                );
+            }
+            assert_eq!(heap.len(), 10);
+            assert_eq!(other.len(), 9);
+            assert!((0..10).all(|value| heap.pop() == Some(value)));
+            assert!((11..20).all(|value| other.pop() == Some(value)));
+        }
+
+        #[test]
+        fn $melded_handles() {
+            use crate::$module::$heap;
+
+            let mut first = $heap::min();
+            let mut second = $heap::min();
+            let mut third = $heap::min();
+            let a = first.push(10);
+            let b = second.push(20);
+            let c = third.push(30);
+            second.meld(first);
+            third.meld(second);
+            third.decrease_key(&a, 3);
+            third.decrease_key(&b, 2);
+            third.decrease_key(&c, 1);
+            assert_eq!(third.pop(), Some(1));
+            assert_eq!(third.pop(), Some(2));
+            assert_eq!(third.pop(), Some(3));
+        }
+    };
+}
+
+meldable_heap_tests!(
+    pairing_heap::PairingHeap,
+    pairing_heap_test,
+    pairing_heap_meld,
+    pairing_heap_decrease_key,
+    pairing_heap_popped_handle,
+    pairing_heap_foreign_handle,
+    pairing_heap_melded_handles
+);
+
+meldable_heap_tests!(
+    fibonacci_heap::FibonacciHeap,
+    fibonacci_heap_test,
+    fibonacci_heap_meld,
+    fibonacci_heap_decrease_key,
+    fibonacci_heap_popped_handle,
+    fibonacci_heap_foreign_handle,
+    fibonacci_heap_melded_handles
+);
+
+macro_rules! persistent_heap_tests {
+    ($heap:ident, $basic:ident, $snapshots:ident) => {
+        #[test]
+        fn $basic() {
+            use crate::persistent_heap::$heap;
+            use rand::seq::SliceRandom;
+
+            let mut values = (0..1000).collect::<Vec<u64>>();
+            values.shuffle(&mut rand::thread_rng());
+
+            let (left, right) = values.split_at(400);
+            let left = left
+                .iter()
+                .fold($heap::min(), |heap, &value| heap.push(value));
+            let right = right
+                .iter()
+                .fold($heap::min(), |heap, &value| heap.push(value));
+            let mut heap = left.merge(&right);
+            assert_eq!(heap.len(), 1000);
+            assert_eq!(left.len(), 400);
+
+            for expected in 0..1000 {
+                let (&top, rest) = heap.pop().unwrap();
+                assert_eq!(top, expected);
+                heap = rest;
+            }
+            assert!(heap.is_empty());
+            assert!(heap.pop().is_none());
+
+            // dropping a large heap must not overflow the stack
+            let heap = (0..100_000).fold($heap::max(), |heap, value| heap.push(value));
+            assert_eq!(heap.peek(), Some(&99_999));
+        }
+
+        #[test]
+        fn $snapshots() {
+            use crate::persistent_heap::$heap;
+
+            let empty = $heap::new(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+            let one = empty.push("ccc");
+            let two = one.push("a");
+            let three = two.push("bb");
+
+            assert!(empty.is_empty());
+            assert_eq!(one.peek(), Some(&"ccc"));
+            assert_eq!(two.peek(), Some(&"a"));
+
+            let (top, rest) = three.pop().unwrap();
+            assert_eq!(*top, "a");
+            assert_eq!(rest.peek(), Some(&"bb"));
+            assert_eq!(three.len(), 3);
+            assert_eq!(three.clone().peek(), Some(&"a"));
+
+            let branch = rest.push("");
+            assert_eq!(branch.peek(), Some(&""));
+            assert_eq!(rest.peek(), Some(&"bb"));
+        }
+    };
+}
+
+persistent_heap_tests!(LeftistHeap, leftist_heap_test, leftist_heap_snapshots);
+persistent_heap_tests!(SkewHeap, skew_heap_test, skew_heap_snapshots);
+
+#[test]
+fn minmax_heap_test() {
+    use crate::minmax_heap::MinMaxHeap;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut heap = MinMaxHeap::default();
+    // sorted reference of the items in the heap
+    let mut expected = Vec::new();
+    for _ in 0..5000 {
+        match rng.gen_range(0, 4) {
+            0 => assert_eq!(
+                heap.pop_min(),
+                if expected.is_empty() {
+                    None
+                } else {
+                    Some(expected.remove(0))
+                }
+            ),
+            1 => assert_eq!(heap.pop_max(), expected.pop()),
+            _ => {
+                let value = rng.gen_range(0, 100);
+                heap.push(value);
+                let idx = expected.binary_search(&value).unwrap_or_else(|idx| idx);
+                expected.insert(idx, value);
+            }
+        }
+        assert_eq!(heap.len(), expected.len());
+        assert_eq!(heap.peek_min(), expected.first());
+        assert_eq!(heap.peek_max(), expected.last());
+    }
+}
+
+#[test]
+fn minmax_heap_from_vec() {
+    use crate::minmax_heap::MinMaxHeap;
+    use rand::seq::SliceRandom;
+
+    for len in 0..50 {
+        let mut values = (0..len).collect::<Vec<u64>>();
+        values.shuffle(&mut rand::thread_rng());
+
+        // reversed comparator: "min" is the largest number
+        let mut heap = MinMaxHeap::from_vec(values, |a: &u64, b: &u64| b.cmp(a));
+        let mut expected = (0..len).collect::<Vec<_>>();
+        for i in 0..len {
+            if i % 2 == 0 {
+                assert_eq!(heap.pop_min(), expected.pop());
+            } else {
+                assert_eq!(heap.pop_max(), Some(expected.remove(0)));
+            }
+        }
+        assert!(heap.is_empty());
+    }
+}
+
+#[test]
+fn radix_heap_test() {
+    use crate::radix_heap::RadixHeap;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut heap = RadixHeap::default();
+    let mut expected: Vec<u64> = Vec::new();
+    for _ in 0..5000 {
+        if rng.gen_bool(0.4) {
+            expected.sort_by(|a, b| b.cmp(a));
+            let popped = heap.pop();
+            assert_eq!(popped.map(|(key, _)| key), expected.pop());
+            if let Some((key, value)) = popped {
+                assert_eq!(key, value);
+            }
+        } else {
+            // monotone: never below the last popped key
+            let bits = rng.gen_range(0, 40);
+            let key = heap.last_key() + rng.gen_range(0, 1 << bits);
+            heap.push(key, key);
+            expected.push(key);
+        }
+        assert_eq!(heap.len(), expected.len());
+    }
+}
+
+#[test]
+#[should_panic(expected = "smaller than the last popped key")]
+fn radix_heap_not_monotone() {
+    use crate::radix_heap::RadixHeap;
+
+    let mut heap = RadixHeap::default();
+    heap.push(10, ());
+    heap.pop();
+    heap.push(5, ());
+}
+
+#[test]
+fn top_k_test() {
+    use crate::top_k::{Largest, TopK};
+    use rand::seq::SliceRandom;
+
+    let mut values = (0..1000).collect::<Vec<u64>>();
+    values.shuffle(&mut rand::thread_rng());
+
+    let largest = TopK::from_iter_with(5, values.iter().copied());
+    assert_eq!(largest.k(), 5);
+    assert_eq!(largest.cutoff(), Some(&995));
+    assert_eq!(largest.into_sorted_vec(), [999, 998, 997, 996, 995]);
+
+    let mut smallest = TopK::smallest(3);
+    smallest.extend(values.iter().copied());
+    assert_eq!(smallest.len(), 3);
+    assert_eq!(smallest.into_sorted_vec(), [0, 1, 2]);
+
+    // items that do not make it are handed back
+    let mut shortest = TopK::new(2, |a: &&str, b: &&str| b.len().cmp(&a.len()));
+    assert_eq!(shortest.push("ccc"), None);
+    assert_eq!(shortest.push("a"), None);
+    assert_eq!(shortest.push("dddd"), Some("dddd"));
+    assert_eq!(shortest.push("bb"), Some("ccc"));
+    assert_eq!(shortest.into_sorted_vec(), ["a", "bb"]);
+
+    let mut none = TopK::largest(0);
+    assert_eq!(none.push(1), Some(1));
+    assert!(none.is_empty());
+
+    // k can come from a config or the command line
+    let k = "4".parse().unwrap();
+    let top = TopK::from_iter_with(k, values.iter().copied());
+    assert_eq!(top.into_sorted_vec(), [999, 998, 997, 996]);
+
+    // or be fixed, to collect into
+    let top: Largest<_, 3> = values.iter().copied().collect();
+    assert_eq!((top.k(), top.len()), (3, 3));
+    assert_eq!(format!("{:?}", top).len(), "[997, 998, 999]".len());
+    assert_eq!(top.into_inner().into_sorted_vec(), [999, 998, 997]);
+}
+
+#[test]
+fn running_median_test() {
+    use crate::median::RunningMedian;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut median = RunningMedian::default();
+    assert_eq!(median.median(), None);
+
+    let mut seen = Vec::new();
+    for _ in 0..500 {
+        let value = rng.gen_range(0, 100);
+        median.push(value);
+        seen.push(value);
+        seen.sort();
+
+        let len = seen.len();
+        assert_eq!(median.len(), len);
+        assert_eq!(median.median(), Some(&seen[(len - 1) / 2]));
+        assert_eq!(
+            median.middle(),
+            Some((&seen[(len - 1) / 2], &seen[len / 2]))
+        );
+    }
+}
+
+#[test]
+fn sliding_quantile_test() {
+    use crate::median::SlidingQuantile;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for &(window, quantile) in &[
+        (1, 0.5),
+        (7, 0.5),
+        (10, 0.5),
+        (50, 0.9),
+        (50, 0.0),
+        (33, 1.0),
+    ] {
+        let mut sliding = SlidingQuantile::new(window, quantile);
+        assert_eq!(sliding.get(), None);
+
+        let mut values = Vec::new();
+        for _ in 0..2000 {
+            // sorted and sawtooth runs keep expired items deep inside the heaps
+            let value = match values.len() / 300 % 3 {
+                0 => rng.gen_range(0, 1000),
+                1 => values.len(),
+                _ => values.len() % 17,
+            };
+            sliding.push(value);
+            values.push(value);
+
+            let mut last = values[values.len().saturating_sub(window)..].to_vec();
+            last.sort();
+            let rank = ((quantile * last.len() as f64).ceil() as usize).clamp(1, last.len());
+            assert_eq!(sliding.len(), last.len());
+            assert_eq!(sliding.get(), Some(&last[rank - 1]));
+        }
+    }
+}
+
+#[test]
+fn concurrent_heap_stress() {
+    use crate::concurrent_heap::ConcurrentHeap;
+    use std::sync::Arc;
+    use std::thread;
+
+    const PRODUCERS: u64 = 8;
+    const ITEMS: u64 = 5000;
+
+    let heap = Arc::new(ConcurrentHeap::min());
+    let consumers = (0..8)
+        .map(|_| {
+            let heap = Arc::clone(&heap);
+            thread::spawn(move || {
+                let mut popped = Vec::new();
+                while let Some(value) = heap.pop_wait() {
+                    popped.push(value);
+                }
+                popped
+            })
+        })
+        .collect::<Vec<_>>();
+    let producers = (0..PRODUCERS)
+        .map(|p| {
+            let heap = Arc::clone(&heap);
+            thread::spawn(move || {
+                for i in 0..ITEMS / 2 {
+                    heap.push(p * ITEMS + i);
+                }
+                heap.push_all((ITEMS / 2..ITEMS).map(|i| p * ITEMS + i));
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    heap.close();
+
+    let mut all = Vec::new();
+    for consumer in consumers {
+        all.extend(consumer.join().unwrap());
+    }
+    all.sort();
+    assert_eq!(all, (0..PRODUCERS * ITEMS).collect::<Vec<_>>());
+    assert!(heap.is_empty());
+}
+
+#[test]
+fn concurrent_heap_order_and_timeout() {
+    use crate::concurrent_heap::ConcurrentHeap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    let heap = ConcurrentHeap::new(|a: &u32, b: &u32| b.cmp(a));
+    heap.push_all(vec![3, 9, 1, 7]);
+    assert_eq!(heap.len(), 4);
+    assert_eq!(heap.peek_with(|top| top.copied()), Some(9));
+    assert_eq!(heap.pop(), Some(9));
+    assert_eq!(heap.pop_wait(), Some(7));
+    assert_eq!(heap.into_heap().into_sorted_vec(), [3, 1]);
+
+    let heap = Arc::new(ConcurrentHeap::min());
+    let start = Instant::now();
+    assert_eq!(heap.pop_wait_timeout(Duration::from_millis(50)), None);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // a waiting consumer is woken up by a push from another thread
+    let producer = {
+        let heap = Arc::clone(&heap);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            heap.push(42);
+        })
+    };
+    assert_eq!(heap.pop_wait_timeout(Duration::from_secs(10)), Some(42));
+    producer.join().unwrap();
+
+    // a timeout too long for a deadline waits like `pop_wait`
+    heap.push(5);
+    assert_eq!(heap.pop_wait_timeout(Duration::MAX), Some(5));
+
+    heap.close();
+    assert!(heap.is_closed());
+    assert_eq!(heap.pop_wait(), None);
+    assert_eq!(heap.pop_wait_timeout(Duration::MAX), None);
+}
+
+#[test]
+fn heap_validate() {
+    use crate::heap::{BinaryHeap, Violation};
+    use std::cell::Cell;
+
+    // a comparator that changes its mind leaves the heap out of order
+    let reversed = Cell::new(false);
+    let mut heap = BinaryHeap::new(
+        |a: &u32, b: &u32| {
+            if reversed.get() {
+                b.cmp(a)
+            } else {
+                a.cmp(b)
+            }
+        },
+    );
+    heap.extend(vec![5, 3, 8, 1]);
+    assert_eq!(heap.validate(), Ok(()));
+
+    reversed.set(true);
+    let violation = heap.validate().unwrap_err();
+    assert_eq!(
+        violation,
+        Violation {
+            parent: 0,
+            child: 1
+        }
+    );
+    assert_eq!(
+        violation.to_string(),
+        "item at index 1 ranks before its parent at index 0"
+    );
+}
+
+#[test]
+fn heap_render() {
+    use crate::heap::{BinaryHeap, DaryHeap};
+
+    let heap = BinaryHeap::from_vec(vec![1, 2, 3, 4, 5, 6], |a: &u32, b: &u32| a.cmp(b));
+    assert_eq!(
+        format!("{:?}", heap),
+        "1\n├── 2\n│   ├── 4\n│   └── 5\n└── 3\n    └── 6"
+    );
+    assert_eq!(
+        heap.to_dot(),
+        "digraph heap {\n    0 [label=\"1\"];\n    1 [label=\"2\"];\n    0 -> 1;\n    \
+         2 [label=\"3\"];\n    0 -> 2;\n    3 [label=\"4\"];\n    1 -> 3;\n    \
+         4 [label=\"5\"];\n    1 -> 4;\n    5 [label=\"6\"];\n    2 -> 5;\n}"
+    );
+
+    let heap = DaryHeap::<_, _, 3>::from_vec(vec!["a", "b", "c", "d", "e"], |a, b| a.cmp(b));
+    assert_eq!(
+        format!("{:?}", heap),
+        "\"a\"\n├── \"b\"\n│   └── \"e\"\n├── \"c\"\n└── \"d\""
+    );
+    assert!(heap.to_dot().contains("0 [label=\"\\\"a\\\"\"];"));
+    assert_eq!(format!("{:?}", BinaryHeap::<u8, _>::min()), "(empty)");
+}
+
+#[test]
+fn scheduler_test() {
+    use crate::scheduler::Scheduler;
+
+    let mut scheduler = Scheduler::default();
+    let first = scheduler.schedule_at(10, "first");
+    let cancelled = scheduler.schedule_at(5, "cancelled");
+    let second = scheduler.schedule_at(10, "second");
+    let moved = scheduler.schedule_at(1, "moved");
+    scheduler.schedule_in(30, "late");
+    assert_eq!(scheduler.len(), 5);
+
+    assert_eq!(scheduler.cancel(cancelled), Some("cancelled"));
+    assert_eq!(scheduler.cancel(cancelled), None);
+    assert!(scheduler.reschedule(moved, 10));
+    assert_eq!(scheduler.next_time(), Some(10));
+
+    // equal timestamps fire in scheduling order
+    let fired = scheduler.advance_to(20);
+    assert_eq!(
+        fired,
+        [
+            (10, first, "first"),
+            (10, second, "second"),
+            (10, moved, "moved")
+        ]
+    );
+    assert_eq!(scheduler.now(), 20);
+    assert!(!scheduler.is_scheduled(first));
+    assert!(!scheduler.reschedule(first, 25));
+    assert!(scheduler.advance_to(29).is_empty());
+
+    assert_eq!(
+        scheduler.pop_next().map(|(time, _, value)| (time, value)),
+        Some((30, "late"))
+    );
+    assert_eq!(scheduler.now(), 30);
+    assert!(scheduler.is_empty());
+    assert_eq!(scheduler.pop_next(), None);
+}
+
+#[test]
+fn scheduler_periodic() {
+    use crate::scheduler::Scheduler;
+
+    let mut scheduler = Scheduler::default();
+    let tick = scheduler.schedule_every(0, 10, "tick");
+    scheduler.schedule_at(20, "once");
+
+    // the tick due at 20 was re-armed after "once" was scheduled
+    let fired = scheduler.advance_to(35);
+    let times = fired
+        .iter()
+        .map(|&(time, _, value)| (time, value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        times,
+        [
+            (0, "tick"),
+            (10, "tick"),
+            (20, "once"),
+            (20, "tick"),
+            (30, "tick")
+        ]
+    );
+    assert!(scheduler.is_scheduled(tick));
+
+    // a periodic event continues from where it was moved to
+    scheduler.reschedule(tick, 100);
+    assert_eq!(scheduler.advance_to(99), []);
+    assert_eq!(scheduler.advance_to(115).len(), 2);
+
+    assert_eq!(scheduler.cancel(tick), Some("tick"));
+    assert!(scheduler.advance_to(1000).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "cannot schedule an event in the past")]
+fn scheduler_past_event() {
+    use crate::scheduler::Scheduler;
+
+    let mut scheduler = Scheduler::default();
+    scheduler.advance_to(10);
+    scheduler.schedule_at(5, ());
+}
+
 #[test]
 fn hashtable_basic() {
     let mut m = HashTable::default();
@@ -185,7 +1011,7 @@ fn hashtable_duplicate_key() {
 
 #[test]
 fn hashtable_single_remove() {
-    let mut m: HashTable<_, u64> = HashTable::default();
+    let mut m: HashTable<&str, u64> = HashTable::default();
 
     m.remove(&"key");
     assert_eq!(m.get(&"key"), None);
@@ -226,7 +1052,8 @@ fn hashtable_remove_nonexistent_item() {
 #[test]
 fn hashtable_resize() {
     let mut m = HashTable::default();
-    let range = 0..m.capacity() * 10;
+    // ten times the initial buckets, to grow the table and shrink it again
+    let range = 0..m.bucket_count() * 10;
 
     for i in range.clone() {
         m.insert(i, i);
@@ -357,3 +1184,1176 @@ fn hashtable_show() {
     assert!(map_str == "{1: 2, 3: 4}" || map_str == "{3: 4, 1: 2}");
     assert_eq!(format!("{:?}", empty), "{}");
 }
+
+#[test]
+fn hashtable_entry() {
+    use crate::hashtable::Entry;
+
+    let mut m = HashTable::default();
+    for word in "a b a c b a".split(' ') {
+        *m.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(m.len(), 3);
+    assert_eq!(m.get(&"a"), Some(&3));
+    assert_eq!(m.get(&"b"), Some(&2));
+    assert_eq!(m.get(&"c"), Some(&1));
+
+    m.entry("a").and_modify(|count| *count *= 10).or_default();
+    m.entry("d").and_modify(|count| *count *= 10).or_default();
+    assert_eq!(m.get(&"a"), Some(&30));
+    assert_eq!(m.get(&"d"), Some(&0));
+    assert_eq!(*m.entry("e").or_insert_with(|| 5), 5);
+    assert_eq!(*m.entry("ee").or_insert_with_key(|key| key.len()), 2);
+    assert_eq!(m.entry("f").key(), &"f");
+    assert_eq!(m.len(), 6);
+
+    match m.entry("a") {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &"a");
+            assert_eq!(entry.insert(7), 30);
+            assert_eq!(entry.get(), &7);
+            assert_eq!(entry.remove_entry(), ("a", 7));
+        }
+        Entry::Vacant(_) => panic!("`a` should be in the table"),
+    }
+    match m.entry("a") {
+        Entry::Occupied(_) => panic!("`a` should have been removed"),
+        Entry::Vacant(entry) => {
+            assert_eq!(entry.key(), &"a");
+            *entry.insert(1) += 1;
+        }
+    }
+    assert_eq!(m.get(&"a"), Some(&2));
+    assert_eq!(m.len(), 6);
+
+    // the table grows while entries are added
+    let mut m = HashTable::default();
+    for i in 0..1000 {
+        m.entry(i).or_insert(i * 2);
+    }
+    assert_eq!(m.len(), 1000);
+    assert!((0..1000).all(|i| m.get(&i) == Some(&(i * 2))));
+
+    // a full table grows only for a new key
+    let mut m = HashTable::default();
+    let capacity = m.capacity();
+    for i in 0..capacity {
+        m.insert(i, i);
+    }
+    let buckets = m.bucket_count();
+    *m.entry(0).or_insert(0) += 1;
+    m.insert(1, 1);
+    assert_eq!(m.bucket_count(), buckets);
+    m.entry(capacity).or_insert(0);
+    assert!(m.bucket_count() > buckets);
+    assert_eq!(m.get(&0), Some(&1));
+}
+
+#[test]
+fn hashtable_insert_after_tombstone() {
+    let mut m = HashTable::default();
+    for i in 0..30 {
+        m.insert(i, i);
+    }
+    for i in 0..15 {
+        m.remove(&i);
+    }
+    // keys further along a probe chain than the tombstones must be found, not duplicated
+    for i in 0..30 {
+        m.insert(i, i + 1);
+    }
+    assert_eq!(m.len(), 30);
+    for i in 0..15 {
+        m.remove(&i);
+    }
+    assert_eq!(m.len(), 15);
+    assert!((15..30).all(|i| m.get(&i) == Some(&(i + 1))));
+}
+
+#[test]
+fn hashtable_access_and_removal() {
+    let mut m = HashTable::default();
+    m.insert(String::from("one"), 1);
+    m.insert(String::from("two"), 2);
+
+    *m.get_mut(&String::from("one")).unwrap() += 10;
+    assert_eq!(m.get(&String::from("one")), Some(&11));
+    assert_eq!(m.get_mut(&String::from("three")), None);
+    assert_eq!(
+        m.get_key_value(&String::from("two")),
+        Some((&String::from("two"), &2))
+    );
+
+    assert_eq!(m.remove(&String::from("one")), Some(11));
+    assert_eq!(m.remove(&String::from("one")), None);
+    assert_eq!(
+        m.remove_entry(&String::from("two")),
+        Some((String::from("two"), 2))
+    );
+    assert!(m.is_empty());
+
+    // the buckets are kept, so the table is usable after clearing it
+    m.insert(String::from("one"), 1);
+    assert_eq!(m.clear(), vec![(String::from("one"), 1)]);
+    m.insert(String::from("two"), 2);
+    assert_eq!(m.len(), 1);
+}
+
+#[test]
+fn hashtable_capacity() {
+    let mut m = HashTable::with_capacity(1000);
+    let capacity = m.capacity();
+    assert!(capacity >= 1000 && is_prime(m.bucket_count()));
+    assert_eq!(capacity, m.bucket_count() * 70 / 100);
+    for i in 0..1000 {
+        m.insert(i, i);
+    }
+    assert_eq!(m.capacity(), capacity);
+
+    m.reserve(500);
+    let capacity = m.capacity();
+    assert!(capacity >= 1500 && is_prime(m.bucket_count()));
+    for i in 1000..1500 {
+        m.insert(i, i);
+    }
+    assert_eq!(m.capacity(), capacity);
+    // enough room already
+    m.reserve(0);
+    assert_eq!(m.capacity(), capacity);
+
+    m.retain(|key, value| {
+        *value += 1;
+        key % 10 == 0
+    });
+    assert_eq!(m.len(), 150);
+    assert!((0..1500).all(|i| m.get(&i).copied() == (i % 10 == 0).then_some(i + 1)));
+
+    m.shrink_to_fit();
+    assert!(m.capacity() < capacity && is_prime(m.bucket_count()));
+    assert_eq!(m.len(), 150);
+    assert!((0..1500).step_by(10).all(|i| m.get(&i) == Some(&(i + 1))));
+
+    m.clear();
+    m.shrink_to_fit();
+    assert_eq!(m.capacity(), HashTable::<u64, u64>::default().capacity());
+
+    // tombstones do not count, so they cannot make reserve shrink the table
+    let mut m = HashTable::with_capacity(1000);
+    let capacity = m.capacity();
+    m.extend((0..capacity).map(|i| (i, i)));
+    for i in 200..capacity {
+        m.remove(&i);
+    }
+    m.reserve(1);
+    assert_eq!(m.capacity(), capacity);
+    assert_eq!(m.len(), 200);
+}
+
+#[test]
+fn hashtable_iterators() {
+    let mut m: HashTable<u64, u64> = (0..100).map(|i| (i, i * i)).collect();
+    for i in 0..50 {
+        m.remove(&(i * 2));
+    }
+
+    let mut iter = m.iter();
+    assert_eq!(iter.len(), 50);
+    iter.next();
+    assert_eq!(iter.size_hint(), (49, Some(49)));
+    let mut items: Vec<_> = m.iter().map(|(&k, &v)| (k, v)).collect();
+    items.sort_unstable();
+    assert_eq!(
+        items,
+        (0..50)
+            .map(|i: u64| (i * 2 + 1, (i * 2 + 1).pow(2)))
+            .collect::<Vec<_>>()
+    );
+
+    let mut keys: Vec<_> = m.keys().copied().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, (0..50).map(|i| i * 2 + 1).collect::<Vec<_>>());
+    assert_eq!(m.values().len(), 50);
+    assert_eq!(m.values().sum::<u64>(), keys.iter().map(|k| k * k).sum());
+
+    for (key, value) in &mut m {
+        *value -= key * key;
+    }
+    m.values_mut().for_each(|value| *value += 1);
+    assert!(m.values().all(|&value| value == 1));
+    assert_eq!((&m).into_iter().count(), 50);
+
+    m.extend((0..10).map(|i| (i, 0)));
+    assert_eq!(m.len(), 55);
+
+    let mut removed: Vec<_> = m.drain_filter(|key, _| key % 3 == 0).collect();
+    removed.sort_unstable();
+    assert_eq!(removed.len(), 19);
+    assert_eq!(removed[..3], [(0, 0), (3, 0), (6, 0)]);
+    assert_eq!(m.len(), 36);
+    assert!(m.keys().all(|key| key % 3 != 0));
+
+    // items the iterator did not get to are kept
+    {
+        let mut drain_filter = m.drain_filter(|_, _| true);
+        assert_eq!(drain_filter.size_hint(), (0, Some(36)));
+        drain_filter.next();
+        drain_filter.next();
+    }
+    assert_eq!(m.len(), 34);
+
+    let mut into_iter = m.clone().into_iter();
+    assert_eq!(into_iter.len(), 34);
+    into_iter.next();
+    assert_eq!(into_iter.len(), 33);
+    assert_eq!(into_iter.count(), 33);
+
+    let mut drain = m.drain();
+    assert_eq!(drain.len(), 34);
+    drain.next();
+    assert_eq!(drain.len(), 33);
+    drop(drain);
+    assert!(m.is_empty());
+    assert_eq!(m.iter().next(), None);
+    m.insert(1, 1);
+    assert_eq!(m.iter().collect::<Vec<_>>(), [(&1, &1)]);
+
+    // a leaked drain leaves the items it did not get to in the table
+    let mut m: HashTable<_, _> = (0..10).map(|i| (i, i)).collect();
+    m.remove(&0);
+    let mut drain = m.drain();
+    let (first, _) = drain.next().unwrap();
+    std::mem::forget(drain);
+    assert_eq!(m.len(), 8);
+    assert_eq!(m.iter().count(), 8);
+    assert!((1..10).all(|i| m.contains_key(&i) == (i != first)));
+}
+
+// Hashes every key to the same value, so that all keys collide.
+#[derive(Default)]
+struct Constant;
+
+impl Hasher for Constant {
+    fn write(&mut self, _: &[u8]) {}
+    fn finish(&self) -> u64 {
+        7
+    }
+}
+
+// seeds make no difference either
+impl crate::hasher::SeedableHasher for Constant {
+    fn with_seed(_: u64) -> Self {
+        Constant
+    }
+}
+
+#[derive(Clone, Default)]
+struct ConstantState;
+
+impl BuildHasher for ConstantState {
+    type Hasher = Constant;
+    fn build_hasher(&self) -> Constant {
+        Constant
+    }
+}
+
+#[test]
+fn hashtable_with_hasher() {
+    use std::collections::hash_map::RandomState;
+
+    let mut m = HashTable::with_hasher(RandomState::new());
+    for i in 0..200 {
+        m.insert(i, i + 1);
+    }
+    assert!((0..200).all(|i| m.get(&i) == Some(&(i + 1))));
+    let clone = m.clone();
+    assert_eq!(clone, m);
+
+    // every key lands in the same bucket, probing has to sort it out
+    let mut m = HashTable::with_capacity_and_hasher(100, ConstantState);
+    let capacity = m.capacity();
+    m.extend((0..100).map(|i| (i, i)));
+    assert_eq!(m.capacity(), capacity);
+    for i in (0..100).step_by(2) {
+        assert_eq!(m.remove(&i), Some(i));
+    }
+    assert_eq!(m.len(), 50);
+    assert!((0..100).all(|i| m.contains_key(&i) == (i % 2 == 1)));
+
+    let m: HashTable<_, _, ConstantState> = (0..10).map(|i| (i, i)).collect();
+    assert_eq!(m.len(), 10);
+}
+
+fn hash_bytes<H: std::hash::Hasher>(mut hasher: H, bytes: &[u8]) -> u64 {
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[test]
+fn hasher_test_vectors() {
+    use crate::hasher::{
+        FnvHasher, Murmur3Hasher, SeedableHasher, SipHasher13, WyHasher, XxHasher64,
+    };
+
+    let fnv = |bytes: &[u8]| hash_bytes(FnvHasher::default(), bytes);
+    assert_eq!(fnv(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(fnv(b"foobar"), 0x85944171f73967e8);
+
+    let murmur3 = |bytes: &[u8]| {
+        let mut hasher = Murmur3Hasher::default();
+        std::hash::Hasher::write(&mut hasher, bytes);
+        hasher.finish128()
+    };
+    assert_eq!(murmur3(b""), (0, 0));
+    assert_eq!(
+        murmur3(b"The quick brown fox jumps over the lazy dog"),
+        (0xe34bbc7bbc071b6c, 0x7a433ca9c49a9347)
+    );
+
+    let xxh64 = |bytes: &[u8]| hash_bytes(XxHasher64::default(), bytes);
+    assert_eq!(xxh64(b""), 0xef46db3751d8e999);
+    assert_eq!(xxh64(b"a"), 0xd24ec4f1a98c6e5b);
+    assert_eq!(xxh64(b"abc"), 0x44bc2cf5ad770999);
+    assert_eq!(
+        xxh64(b"Nobody inspects the spammish repetition"),
+        0xfbcea83c8a378bf1
+    );
+
+    let wyhash = |bytes: &[u8], seed| hash_bytes(WyHasher::with_seed(seed), bytes);
+    assert_eq!(wyhash(b"", 0), 0x93228a4de0eec5a2);
+    assert_eq!(wyhash(b"a", 1), 0xc5bac3db178713c4);
+    assert_eq!(wyhash(b"abc", 2), 0xa97f2f7b1d9b3314);
+    assert_eq!(wyhash(b"message digest", 3), 0x786d1f1df3801df4);
+    assert_eq!(wyhash(b"abcdefghijklmnopqrstuvwxyz", 4), 0xdca5a8138ad37c87);
+    assert_eq!(
+        wyhash(
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+            5
+        ),
+        0xb9e734f117cfaf70
+    );
+    assert_eq!(
+        wyhash(
+            b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+            6
+        ),
+        0x6cc5eab49a92d617
+    );
+
+    // std's `DefaultHasher` is SipHash-1-3 with both keys zero
+    let bytes: Vec<u8> = (0..64).collect();
+    for len in 0..bytes.len() {
+        let expected = hash_bytes(
+            std::collections::hash_map::DefaultHasher::new(),
+            &bytes[..len],
+        );
+        assert_eq!(hash_bytes(SipHasher13::default(), &bytes[..len]), expected);
+    }
+    assert_ne!(
+        hash_bytes(SipHasher13::with_keys(1, 2), b"key"),
+        hash_bytes(SipHasher13::default(), b"key")
+    );
+}
+
+#[test]
+fn hasher_split_writes() {
+    use crate::hasher::{Murmur3Hasher, SeedableHasher, SipHasher13, WyHasher, XxHasher64};
+
+    // the buffered hashers must not care how the input is cut into writes
+    fn check<H: SeedableHasher>(bytes: &[u8]) {
+        let whole = hash_bytes(H::with_seed(42), bytes);
+        for step in 1..=bytes.len().min(50) {
+            let mut hasher = H::with_seed(42);
+            for chunk in bytes.chunks(step) {
+                hasher.write(chunk);
+            }
+            assert_eq!(
+                hasher.finish(),
+                whole,
+                "{} bytes in chunks of {}",
+                bytes.len(),
+                step
+            );
+        }
+    }
+
+    let bytes: Vec<u8> = (0..200u8).map(|b| b.wrapping_mul(151)).collect();
+    for len in 0..bytes.len() {
+        check::<Murmur3Hasher>(&bytes[..len]);
+        check::<XxHasher64>(&bytes[..len]);
+        check::<WyHasher>(&bytes[..len]);
+        check::<SipHasher13>(&bytes[..len]);
+    }
+}
+
+#[test]
+fn hasher_quality() {
+    use crate::hasher::{
+        avalanche_bias, bucket_chi_squared, FnvHasher, HasherDJB2, Murmur3Hasher, SeededState,
+        SipHasher13, WyHasher, XxHasher64,
+    };
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    // the prime capacities a table goes through while it grows
+    let mut m = HashTable::default();
+    let mut capacities = vec![m.bucket_count()];
+    for i in 0..50_000 {
+        m.insert(i, ());
+        if m.bucket_count() != *capacities.last().unwrap() {
+            capacities.push(m.bucket_count());
+        }
+    }
+    assert!(capacities.len() > 5);
+
+    fn measure<S: BuildHasher>(state: S, capacities: &[usize]) -> (f64, f64) {
+        let bias = avalanche_bias(&state, 2_000);
+        // low-entropy keys: consecutive integers and short strings
+        let chi_squared = capacities
+            .iter()
+            .flat_map(|&capacity| {
+                let ints = bucket_chi_squared(&state, 0..capacity as u64 * 4, capacity);
+                let strings = bucket_chi_squared(
+                    &state,
+                    (0..capacity * 4).map(|i| format!("key{}", i)),
+                    capacity,
+                );
+                vec![ints, strings]
+            })
+            .fold(0.0, f64::max);
+        (bias, chi_squared)
+    }
+
+    let good = [
+        (
+            "murmur3",
+            measure(SeededState::<Murmur3Hasher>::new(1), &capacities),
+        ),
+        (
+            "xxh64",
+            measure(SeededState::<XxHasher64>::new(1), &capacities),
+        ),
+        (
+            "wyhash",
+            measure(SeededState::<WyHasher>::new(1), &capacities),
+        ),
+        (
+            "siphash13",
+            measure(SeededState::<SipHasher13>::new(1), &capacities),
+        ),
+    ];
+    for (name, (bias, chi_squared)) in good.iter() {
+        assert!(*bias < 0.1, "{} avalanche bias {}", name, bias);
+        assert!(*chi_squared < 2.0, "{} chi-squared {}", name, chi_squared);
+    }
+
+    let (fnv_bias, _) = measure(BuildHasherDefault::<FnvHasher>::default(), &capacities);
+    let (djb2_bias, _) = measure(BuildHasherDefault::<HasherDJB2>::default(), &capacities);
+    // both end with a multiplication, so a flipped bit in the last byte never reaches lower output bits
+    assert!(fnv_bias > 0.4);
+    assert!(djb2_bias > 0.4);
+}
+
+#[test]
+fn hashtable_borrowed_lookups() {
+    let mut m: HashTable<String, i32> = HashTable::default();
+    m.insert(String::from("one"), 1);
+    m.insert(String::from("two"), 2);
+
+    assert_eq!(m.get("one"), Some(&1));
+    assert_eq!(m.get_key_value("two"), Some((&String::from("two"), &2)));
+    assert!(m.contains_key("two"));
+    assert!(!m.contains_key("three"));
+    *m.get_mut("two").unwrap() += 1;
+    assert_eq!(m["two"], 3);
+    assert_eq!(m[&String::from("one")], 1);
+
+    assert_eq!(m.remove("one"), Some(1));
+    assert_eq!(m.remove_entry("two"), Some((String::from("two"), 3)));
+    assert!(m.is_empty());
+
+    let mut m: HashTable<Vec<u8>, ()> = HashTable::default();
+    m.insert(b"bytes".to_vec(), ());
+    assert!(m.contains_key(&b"bytes"[..]));
+}
+
+#[test]
+#[should_panic(expected = "key not found")]
+fn hashtable_index_missing_key() {
+    let m: HashTable<String, i32> = HashTable::default();
+    let _ = m["missing"];
+}
+
+// Tests every hash table goes through: a few string keys, then random
+// operations against std's map that grow the table and shrink it again. `$new`
+// builds the table for the latter and `$check` runs after every operation.
+macro_rules! hashtable_tests {
+    ($module:ident::$table:ident, $string_keys:ident, $random_ops:ident) => {
+        hashtable_tests!(
+            $module::$table,
+            $string_keys,
+            $random_ops,
+            $table::default(),
+            |_m| ()
+        );
+    };
+    (
+        $module:ident::$table:ident,
+        $string_keys:ident,
+        $random_ops:ident,
+        $new:expr,
+        |$m:ident| $check:expr
+    ) => {
+        #[test]
+        fn $string_keys() {
+            use crate::$module::$table;
+
+            let mut m = $table::default();
+            m.insert(String::from("one"), 1);
+            m.insert(String::from("two"), 2);
+            m.insert(String::from("one"), 3);
+            assert_eq!(m.len(), 2);
+            assert_eq!(m["one"], 3);
+            *m.get_mut("two").unwrap() += 1;
+            assert_eq!(m.get_key_value("two"), Some((&String::from("two"), &3)));
+            assert_eq!(m.remove("one"), Some(3));
+            assert_eq!(m.remove("one"), None);
+            assert!(!m.contains_key("one"));
+            assert_eq!(format!("{:?}", m), r#"{"two": 3}"#);
+        }
+
+        #[test]
+        fn $random_ops() {
+            use crate::$module::$table;
+            use std::collections::HashMap;
+
+            let mut rng = thread_rng();
+            let mut $m = $new;
+            let mut expected = HashMap::new();
+            for round in 0..20_000 {
+                let key = rng.gen_range(0, if round < 10_000 { 5_000 } else { 300 });
+                if rng.gen_range(0, 3) == 0 {
+                    assert_eq!($m.remove(&key), expected.remove(&key));
+                } else {
+                    $m.insert(key, round);
+                    expected.insert(key, round);
+                }
+                assert_eq!($m.len(), expected.len());
+                $check;
+            }
+            assert!(expected
+                .iter()
+                .all(|(key, value)| $m.get(key) == Some(value)));
+            assert!($m.keys().all(|key| expected.contains_key(key)));
+            assert_eq!($m.iter().count(), expected.len());
+        }
+    };
+}
+
+hashtable_tests!(
+    hashtable::HashTable,
+    hashtable_string_keys,
+    hashtable_random_ops,
+    HashTable::default(),
+    |m| assert!(m.len() + m.tombstones() <= m.capacity())
+);
+
+hashtable_tests!(
+    robin_hood::RobinHoodHashTable,
+    robin_hood_hashtable_string_keys,
+    robin_hood_hashtable_random_ops
+);
+
+hashtable_tests!(
+    chained::ChainedHashTable,
+    chained_hashtable_string_keys,
+    chained_hashtable_random_ops
+);
+
+hashtable_tests!(
+    cuckoo::CuckooHashTable,
+    cuckoo_hashtable_string_keys,
+    cuckoo_hashtable_random_ops,
+    CuckooHashTable::with_stash(4),
+    |m| assert!(m.stash_len() <= 4)
+);
+
+hashtable_tests!(
+    flat::FlatHashTable,
+    flat_hashtable_string_keys,
+    flat_hashtable_random_ops
+);
+
+#[test]
+fn robin_hood_hashtable() {
+    use crate::robin_hood::RobinHoodHashTable;
+
+    let mut m: RobinHoodHashTable<_, _> = (0..5_000).map(|i| (i, i)).collect();
+    for i in (0..5_000).step_by(3) {
+        m.remove(&i);
+    }
+    // the table keeps probe distances far below its size
+    assert!(m.max_probe_distance() < 32);
+
+    let len = m.len();
+    let clone = m.clone();
+    assert_eq!(m.clear().len(), len);
+    assert!(m.is_empty());
+    assert_eq!(clone.len(), len);
+    let collected: RobinHoodHashTable<_, _> = (0..100).map(|i| (i, i)).collect();
+    assert_eq!(collected.values().sum::<i32>(), 4950);
+}
+
+#[test]
+fn robin_hood_hashtable_api() {
+    use crate::robin_hood::{Entry, RobinHoodHashTable};
+
+    let mut m = RobinHoodHashTable::default();
+    for word in "the quick brown fox jumps over the lazy dog the end".split(' ') {
+        *m.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(m["the"], 3);
+    m.entry("fox").and_modify(|count| *count += 10).or_default();
+    assert_eq!(m["fox"], 11);
+    match m.entry("dog") {
+        Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("dog", 1)),
+        Entry::Vacant(_) => unreachable!(),
+    }
+    match m.entry("cat") {
+        Entry::Occupied(_) => unreachable!(),
+        Entry::Vacant(entry) => assert_eq!(*entry.insert(7), 7),
+    }
+    assert_eq!(m.len(), 9);
+
+    let mut m: RobinHoodHashTable<_, _> = (0..1000).map(|i| (i, 0)).collect();
+    let clone = m.clone();
+    assert_eq!(clone, m);
+    for (_, value) in &mut m {
+        *value = -1;
+    }
+    assert!(m.values().all(|&value| value == -1));
+    assert_ne!(clone, m);
+    m.values_mut().for_each(|value| *value = 1);
+    assert_eq!((&m).into_iter().len(), 1000);
+    assert_eq!(m.keys().len(), 1000);
+
+    let removed: Vec<_> = m.drain_filter(|key, _| key % 2 == 0).collect();
+    assert_eq!(removed.len(), 500);
+    assert!(removed.iter().all(|(key, _)| key % 2 == 0));
+    // items the iterator did not get to are kept
+    m.drain_filter(|_, _| true);
+    assert_eq!(m.len(), 500);
+    m.retain(|key, _| key % 3 != 0);
+    let expected: Vec<_> = (0..1000)
+        .filter(|key| key % 2 != 0 && key % 3 != 0)
+        .collect();
+    assert_eq!(m.len(), expected.len());
+    assert!(expected.iter().all(|key| m.contains_key(key)));
+
+    let mut into_iter = m.clone().into_iter();
+    assert_eq!(into_iter.len(), expected.len());
+    into_iter.next();
+    assert_eq!(into_iter.count(), expected.len() - 1);
+
+    // a leaked drain leaves the items it did not get to reachable
+    let mut drain = m.drain();
+    assert_eq!(drain.len(), expected.len());
+    drain.next();
+    std::mem::forget(drain);
+    assert_eq!(m.len(), expected.len() - 1);
+    assert_eq!(m.keys().filter(|key| m.contains_key(key)).count(), m.len());
+    m.drain();
+    assert!(m.is_empty());
+
+    let mut m = RobinHoodHashTable::with_capacity(10);
+    let buckets = m.bucket_count();
+    m.reserve(1000);
+    assert!(m.capacity() >= 1000);
+    m.extend((0..1000).map(|i| (i, i)));
+    assert!(m.bucket_count() > buckets);
+    m.retain(|&key, _| key < 10);
+    m.shrink_to_fit();
+    assert_eq!(m.bucket_count(), buckets);
+    assert_eq!(m.len(), 10);
+}
+
+#[test]
+fn robin_hood_hashtable_removal_while_iterating() {
+    use crate::robin_hood::RobinHoodHashTable;
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashMap;
+
+    // removals shift the items after them back, chains wrap around the end
+    // of the buckets, and every key has to stay reachable
+    let mut rng = thread_rng();
+    let mut m = RobinHoodHashTable::with_hasher(RandomState::new());
+    let mut expected = HashMap::new();
+    for round in 0..300 {
+        for _ in 0..rng.gen_range(0, 200) {
+            let key = rng.gen_range(0, 1_000);
+            m.insert(key, round);
+            expected.insert(key, round);
+        }
+        let modulus = rng.gen_range(2, 6);
+        match round % 3 {
+            0 => {
+                // every item is looked at exactly once
+                let mut calls = 0;
+                m.retain(|key, _| {
+                    calls += 1;
+                    key % modulus != 0
+                });
+                assert_eq!(calls, expected.len());
+                expected.retain(|key, _| key % modulus != 0);
+            }
+            1 => {
+                for (key, _) in m.drain_filter(|key, _| key % modulus == 0).take(20) {
+                    assert!(expected.remove(&key).is_some());
+                }
+            }
+            _ => {
+                for (key, _) in m.drain_filter(|key, _| key % modulus == 0) {
+                    assert!(expected.remove(&key).is_some());
+                }
+                assert!(expected.keys().all(|key| key % modulus != 0));
+            }
+        }
+        assert_eq!(m.len(), expected.len());
+        assert!(expected
+            .iter()
+            .all(|(key, value)| m.get(key) == Some(value)));
+        assert_eq!(m.iter().count(), expected.len());
+    }
+}
+
+#[test]
+fn hashtable_probe_strategies() {
+    use crate::hashtable::ProbeStrategy;
+    use std::collections::HashMap;
+
+    // every key hashes to the same bucket, so all of them sit on one probe sequence
+    for &strategy in &[
+        ProbeStrategy::Linear,
+        ProbeStrategy::Quadratic,
+        ProbeStrategy::Triangular,
+        ProbeStrategy::DoubleHashing,
+    ] {
+        let mut m = HashTable::with_probe_strategy(strategy);
+        assert_eq!(m.probe_strategy(), strategy);
+        let mut expected = HashMap::new();
+        let mut rng = thread_rng();
+        for round in 0..5_000 {
+            let key = rng.gen_range(0, 2_000);
+            if rng.gen_range(0, 3) == 0 {
+                assert_eq!(m.remove(&key), expected.remove(&key));
+            } else {
+                m.insert(key, round);
+                expected.insert(key, round);
+            }
+        }
+        assert_eq!(m.len(), expected.len());
+        assert!(expected
+            .iter()
+            .all(|(key, value)| m.get(key) == Some(value)));
+
+        let capacity = m.bucket_count();
+        match strategy {
+            ProbeStrategy::Triangular => assert!(capacity.is_power_of_two()),
+            ProbeStrategy::Quadratic => assert!(is_prime(capacity) && capacity % 4 == 3),
+            _ => assert!(is_prime(capacity)),
+        }
+        assert_eq!(m.clone().probe_strategy(), strategy);
+
+        // fill the table up to its load limit and punch holes: the probe
+        // sequences have to reach every bucket
+        let mut m = HashTable::with_hasher_and_probe_strategy(ConstantState, strategy);
+        let buckets = m.bucket_count();
+        let full = m.capacity();
+        for i in 0..full {
+            m.insert(i, i);
+        }
+        assert_eq!(m.bucket_count(), buckets);
+        for i in (0..full).step_by(3) {
+            m.remove(&i);
+        }
+        for i in (0..full).step_by(3) {
+            m.insert(i, i + 1);
+        }
+        assert_eq!(m.len(), full);
+        assert!((0..full).all(|i| m.get(&i) == Some(&(i + (i % 3 == 0) as usize))));
+    }
+}
+
+#[test]
+fn chained_hashtable() {
+    use crate::chained::{ChainedHashTable, Entry};
+
+    let mut m = ChainedHashTable::default();
+    for word in "a b a c b a".split(' ') {
+        *m.entry(word.to_string()).or_insert(0) += 1;
+    }
+    assert_eq!(m.len(), 3);
+    assert_eq!(m["a"], 3);
+    m.entry("b".to_string()).and_modify(|count| *count *= 10);
+    assert_eq!(m.get("b"), Some(&20));
+    match m.entry("c".to_string()) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.insert(5), 1);
+            assert_eq!(entry.remove_entry(), ("c".to_string(), 5));
+        }
+        Entry::Vacant(_) => panic!("`c` should be in the table"),
+    }
+    assert!(!m.contains_key("c"));
+    *m.get_mut("a").unwrap() += 1;
+    assert_eq!(m.get_key_value("a"), Some((&"a".to_string(), &4)));
+    assert_eq!(m.remove("a"), Some(4));
+    assert_eq!(format!("{:?}", m), r#"{"b": 20}"#);
+
+    let mut m: ChainedHashTable<_, _> = (0..1000).map(|i| (i, i)).collect();
+    assert!(is_prime(m.bucket_count()));
+    assert!(m.len() <= m.capacity());
+
+    let clone = m.clone();
+    assert_eq!(clone, m);
+    for (_, value) in &mut m {
+        *value = -1;
+    }
+    assert!(m.values().all(|&value| value == -1));
+    assert_ne!(clone, m);
+    m.values_mut().for_each(|value| *value = 1);
+    assert_eq!(m.iter().len(), 1000);
+    assert_eq!(m.keys().len(), 1000);
+
+    let removed: Vec<_> = m.drain_filter(|key, _| key % 2 == 0).collect();
+    assert_eq!(removed.len(), 500);
+    assert!(removed.iter().all(|(key, _)| key % 2 == 0));
+
+    // items the iterator did not get to are kept
+    m.drain_filter(|_, _| true);
+    assert_eq!(m.len(), 500);
+    let drained: Vec<_> = {
+        let mut drain_filter = m.drain_filter(|_, _| true);
+        assert_eq!(drain_filter.size_hint(), (0, Some(500)));
+        drain_filter.by_ref().take(2).collect()
+    };
+    assert_eq!(m.len(), 498);
+    m.extend(drained);
+    m.retain(|key, _| key % 3 != 0);
+    assert_eq!(
+        m.len(),
+        (0..1000).filter(|key| key % 2 != 0 && key % 3 != 0).count()
+    );
+    let len = m.len();
+    let mut drain = m.drain();
+    assert_eq!(drain.len(), len);
+    drain.next();
+    drop(drain);
+    assert!(m.is_empty());
+    m.insert(1, 1);
+    assert_eq!(m.clone().into_iter().collect::<Vec<_>>(), [(1, 1)]);
+
+    let mut m: ChainedHashTable<_, _> = (0..1000).map(|i| (i, i)).collect();
+    m.reserve(10_000);
+    let capacity = m.capacity();
+    m.extend((1000..11_000).map(|i| (i, i)));
+    assert_eq!(m.capacity(), capacity);
+    m.retain(|&key, _| key < 10);
+    m.shrink_to_fit();
+    assert!(m.capacity() < capacity);
+    assert_eq!(m.clear().len(), 10);
+}
+
+#[test]
+fn chained_hashtable_collisions() {
+    use crate::chained::ChainedHashTable;
+
+    // every key lands in the same chain
+    let mut m = ChainedHashTable::with_hasher(ConstantState);
+    for i in 0..5_000 {
+        m.insert(i, i);
+    }
+    assert_eq!(m.max_chain_len(), 5_000);
+    for i in (0..5_000).step_by(2) {
+        assert_eq!(m.remove(&i), Some(i));
+    }
+    assert!((0..5_000).all(|i| m.contains_key(&i) == (i % 2 == 1)));
+}
+
+#[test]
+fn cuckoo_hashtable() {
+    use crate::cuckoo::CuckooHashTable;
+
+    let mut m = CuckooHashTable::default();
+    for i in 0..1000 {
+        m.insert(i, i);
+    }
+    assert!(is_prime(m.bucket_count() / 2));
+    assert!(m.len() <= m.capacity());
+
+    let mut items = m.clear();
+    items.sort_unstable();
+    assert_eq!(items, (0..1000).map(|i| (i, i)).collect::<Vec<_>>());
+    assert!(m.is_empty());
+
+    let m: CuckooHashTable<_, _> = (0..1000).map(|i| (i, i)).collect();
+    assert_eq!(m.values().sum::<i32>(), 499_500);
+    assert_eq!(m.into_iter().count(), 1000);
+}
+
+#[test]
+fn cuckoo_hashtable_stash() {
+    use crate::cuckoo::CuckooHashTable;
+    use crate::hasher::{SeedableHasher, WyHasher};
+    use std::hash::Hasher;
+
+    // every key goes to one of the first two buckets of each array, so that
+    // five keys can never all fit into the arrays
+    struct TwoBuckets(WyHasher);
+    impl Hasher for TwoBuckets {
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.write(bytes);
+        }
+        fn finish(&self) -> u64 {
+            self.0.finish() % 2
+        }
+    }
+    impl SeedableHasher for TwoBuckets {
+        fn with_seed(seed: u64) -> Self {
+            TwoBuckets(WyHasher::with_seed(seed))
+        }
+    }
+
+    let mut m = CuckooHashTable::<_, _, TwoBuckets>::with_capacity_and_stash(0, 2);
+    for i in 0..6 {
+        m.insert(i, i * 10);
+    }
+    assert_eq!(m.len(), 6);
+    assert_eq!(m.stash_len(), 2);
+    assert!((0..6).all(|i| m.get(&i) == Some(&(i * 10))));
+    assert_eq!(m.remove(&3), Some(30));
+    assert!((0..6).all(|i| m.contains_key(&i) == (i != 3)));
+
+    // Without a stash, three keys that share both buckets make the table
+    // reseed. Each table has its own seeds, so some of them run into that.
+    let mut rehashes = 0;
+    for _ in 0..500 {
+        let mut m = CuckooHashTable::<_, _, TwoBuckets>::with_capacity_and_stash(0, 0);
+        for i in 0..3 {
+            m.insert(i, i);
+        }
+        assert_eq!(m.stash_len(), 0);
+        assert!((0..3).all(|i| m.get(&i) == Some(&i)));
+        rehashes += m.rehashes();
+    }
+    assert!(rehashes > 0);
+
+    // growing alone is not a rehash, and with room in the stash no key ever
+    // needs one
+    let mut m = CuckooHashTable::with_stash(1_000);
+    let buckets = m.bucket_count();
+    for i in 0..1_000 {
+        m.insert(i, i);
+    }
+    assert!(m.bucket_count() > buckets);
+    assert_eq!(m.rehashes(), 0);
+}
+
+#[test]
+#[should_panic(expected = "cuckoo hashing keeps failing")]
+fn cuckoo_hashtable_constant_hasher() {
+    use crate::cuckoo::CuckooHashTable;
+
+    // every key gets the same two buckets and the stash holds one more, no
+    // seeds or sizes make room for a fourth key
+    let mut m = CuckooHashTable::<_, _, Constant>::with_capacity_and_stash(0, 1);
+    for i in 0..4 {
+        m.insert(i, i);
+    }
+}
+
+#[test]
+fn flat_hashtable() {
+    use crate::flat::FlatHashTable;
+
+    let mut m: FlatHashTable<_, _> = (0..1000).map(|i| (i, i)).collect();
+    assert_eq!(m.bucket_count() % 16, 0);
+    assert!(m.bucket_count().is_power_of_two());
+
+    let clone = m.clone();
+    assert_eq!(m.clear().len(), 1000);
+    assert!(m.is_empty());
+    assert_eq!(clone.len(), 1000);
+    assert_eq!(clone.into_iter().count(), 1000);
+    let collected: FlatHashTable<_, _> = (0..100).map(|i| (i, i)).collect();
+    assert_eq!(collected.values().sum::<i32>(), 4950);
+
+    // growing only when 7/8 of the slots are used
+    let mut m: FlatHashTable<_, _> = (0..56).map(|i| (i, i)).collect();
+    assert_eq!((m.capacity(), m.bucket_count()), (56, 64));
+    m.insert(56, 56);
+    assert_eq!((m.capacity(), m.bucket_count()), (112, 128));
+}
+
+#[test]
+fn flat_hashtable_collisions() {
+    use crate::flat::FlatHashTable;
+
+    // Every key gets the same hash, so the same first group and the same
+    // fingerprint, and lookups have to compare the keys of the whole chain.
+    let mut m = FlatHashTable::with_hasher(ConstantState);
+    for i in 0..200 {
+        m.insert(i, i * 2);
+    }
+    assert_eq!(m.len(), 200);
+    assert!((0..200).all(|i| m.get(&i) == Some(&(i * 2))));
+    assert_eq!(m.get(&200), None);
+
+    // removing from full groups leaves tombstones that lookups must go past
+    for i in (0..200).step_by(2) {
+        assert_eq!(m.remove(&i), Some(i * 2));
+    }
+    assert!((0..200).all(|i| m.get(&i) == (i % 2 == 1).then_some(&(i * 2))));
+
+    // churning through the tombstones rehashes in place instead of growing
+    let capacity = m.capacity();
+    for round in 0..10_000 {
+        m.insert(1_000 + round, round);
+        assert_eq!(m.remove(&(1_000 + round)), Some(round));
+    }
+    assert_eq!(m.len(), 100);
+    assert_eq!(m.capacity(), capacity);
+    assert!((0..200).all(|i| m.contains_key(&i) == (i % 2 == 1)));
+}
+
+#[test]
+fn hashtable_tombstones() {
+    use crate::hashtable::ProbeStrategy;
+
+    let mut m = HashTable::with_capacity(500);
+    let capacity = m.capacity();
+    for i in 0..500 {
+        m.insert(i, i);
+    }
+    for i in 0..250 {
+        m.remove(&i);
+    }
+    assert_eq!(m.tombstones(), 250);
+    // inserting into a tombstone's bucket takes it over
+    m.entry(0).or_insert(0);
+    assert_eq!(m.len(), 251);
+    assert_eq!(m.tombstones(), 249);
+
+    // removals of every kind turn items into tombstones, as long as the
+    // table stays loaded enough not to shrink
+    let occupied = m.len() + m.tombstones();
+    m.retain(|&key, _| key % 3 != 0);
+    m.drain_filter(|&key, _| key % 5 == 0).for_each(drop);
+    if let crate::hashtable::Entry::Occupied(entry) = m.entry(251) {
+        entry.remove();
+    }
+    assert_eq!(m.len() + m.tombstones(), occupied);
+    m.compact();
+    assert_eq!(m.tombstones(), 0);
+    assert_eq!(m.capacity(), capacity);
+    assert!(
+        (0..500).all(|i| m.contains_key(&i) == (i >= 250 && i % 3 != 0 && i % 5 != 0 && i != 251))
+    );
+
+    // Churning through a fixed set of keys must not leave the table full of
+    // tombstones, nor make it grow: they are dropped by rehashing in place.
+    for strategy in [
+        ProbeStrategy::Linear,
+        ProbeStrategy::Quadratic,
+        ProbeStrategy::Triangular,
+        ProbeStrategy::DoubleHashing,
+    ] {
+        let mut m = HashTable::with_probe_strategy(strategy);
+        for i in 0..15 {
+            m.insert(i, i);
+        }
+        let capacity = m.capacity();
+        for round in 0..10_000 {
+            m.insert(100 + round, round);
+            assert_eq!(m.remove(&(100 + round)), Some(round));
+            assert!(m.len() + m.tombstones() <= m.capacity());
+        }
+        assert_eq!(m.capacity(), capacity);
+        assert_eq!(m.len(), 15);
+        assert!((0..15).all(|i| m.get(&i) == Some(&i)));
+    }
+}
+
+#[test]
+fn hashtable_remove_then_shrink() {
+    let mut m = HashTable::default();
+    for i in 0..1000 {
+        m.insert(i, i);
+    }
+    let buckets = m.bucket_count();
+    // a miss does not shrink the table
+    assert_eq!(m.remove(&1000), None);
+    assert_eq!(m.bucket_count(), buckets);
+
+    // the table shrinks only once the removals bring the load below 10%
+    let mut removed = 0;
+    while m.bucket_count() == buckets {
+        assert_eq!(m.remove(&removed), Some(removed));
+        removed += 1;
+    }
+    assert_eq!(m.len(), 1000 - removed);
+    assert!(m.len() * 100 / buckets < 10);
+    assert!((m.len() + 1) * 100 / buckets >= 10);
+    // shrinking rehashes the items, so no tombstones are left
+    assert_eq!(m.tombstones(), 0);
+    assert!((0..1000).all(|i| m.get(&i) == (i >= removed).then_some(&i)));
+
+    // the other ways of removing shrink the table the same way
+    let full = || (0..1000).map(|i| (i, i)).collect::<HashTable<_, _>>();
+    let buckets = full().bucket_count();
+    let mut m = full();
+    m.retain(|&key, _| key < 50);
+    assert!(m.bucket_count() < buckets);
+    assert_eq!(m.tombstones(), 0);
+
+    let mut m = full();
+    m.drain_filter(|&key, _| key >= 50).for_each(drop);
+    assert!(m.bucket_count() < buckets);
+    assert_eq!(m.tombstones(), 0);
+
+    let mut m = full();
+    for i in 50..1000 {
+        if let crate::hashtable::Entry::Occupied(entry) = m.entry(i) {
+            entry.remove();
+        }
+    }
+    assert!(m.bucket_count() < buckets);
+    assert!((0..1000).all(|i| m.contains_key(&i) == (i < 50)));
+}
+
+#[test]
+fn hashtable_capacity_counts_items() {
+    use crate::chained::ChainedHashTable;
+    use crate::cuckoo::CuckooHashTable;
+    use crate::flat::FlatHashTable;
+    use crate::robin_hood::RobinHoodHashTable;
+
+    // `capacity` items fit without growing, one more grows the table
+    macro_rules! check {
+        ($table:ident) => {
+            for requested in [0, 1, 100, 1000] {
+                let mut m = $table::with_capacity(requested);
+                let capacity = m.capacity();
+                let buckets = m.bucket_count();
+                assert!(capacity >= requested);
+                m.extend((0..capacity as u64).map(|i| (i, i)));
+                assert_eq!((m.capacity(), m.bucket_count()), (capacity, buckets));
+                m.insert(capacity as u64, 0);
+                assert!(m.capacity() > capacity && m.bucket_count() > buckets);
+            }
+        };
+    }
+    check!(HashTable);
+    check!(RobinHoodHashTable);
+    check!(ChainedHashTable);
+    check!(CuckooHashTable);
+    check!(FlatHashTable);
+}
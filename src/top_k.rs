@@ -0,0 +1,155 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Deref;
+use std::slice;
+
+use crate::heap::BinaryHeap;
+
+/// Keeps the `k` items that rank last according to `cmp` out of everything
+/// pushed into it, so `|a, b| a.cmp(b)` collects the `k` largest items.
+///
+/// The kept items sit in a heap whose top is the weakest of them, so each
+/// push costs O(log k) and memory stays bounded no matter how long the stream.
+pub struct TopK<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    heap: BinaryHeap<T, F>,
+    k: usize,
+}
+
+impl<T: Ord> TopK<T, fn(&T, &T) -> Ordering> {
+    pub fn largest(k: usize) -> Self {
+        Self::new(k, |a, b| a.cmp(b))
+    }
+
+    pub fn smallest(k: usize) -> Self {
+        Self::new(k, |a, b| b.cmp(a))
+    }
+
+    // keeps the `k` largest items of `iter`
+    pub fn from_iter_with<I: IntoIterator<Item = T>>(k: usize, iter: I) -> Self {
+        let mut top = Self::largest(k);
+        top.extend(iter);
+        top
+    }
+}
+
+impl<T, F> TopK<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    pub fn new(k: usize, cmp: F) -> Self {
+        Self {
+            heap: BinaryHeap::new(cmp),
+            k,
+        }
+    }
+
+    // returns the item that did not make it into the top, if any
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.heap.len() < self.k {
+            self.heap.push(value);
+            return None;
+        }
+        let beats_weakest = match self.heap.peek() {
+            Some(weakest) => (self.heap.comparator())(&value, weakest) == Ordering::Greater,
+            // `k` is zero
+            None => false,
+        };
+        if !beats_weakest {
+            return Some(value);
+        }
+        // the replaced item is sifted down once the guard is dropped
+        let mut weakest = self.heap.peek_mut().unwrap();
+        Some(mem::replace(&mut *weakest, value))
+    }
+
+    // the weakest kept item, the one to beat once `k` items were seen
+    pub fn cutoff(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    // how many items are kept at most
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    // the items are returned in the order they are stored
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    // the items are returned in the order they are stored
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap.into_vec()
+    }
+
+    // the items are returned best first
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut values = self.heap.into_sorted_vec();
+        values.reverse();
+        values
+    }
+}
+
+impl<T, F> Extend<T> for TopK<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, F> Debug for TopK<T, F>
+where
+    T: Debug,
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.heap.iter()).finish()
+    }
+}
+
+/// A `TopK` of the `K` largest items, for collecting into when `k` is known
+/// up front: `let top: Largest<_, 10> = scores.collect();`.
+pub struct Largest<T, const K: usize>(TopK<T, fn(&T, &T) -> Ordering>);
+
+impl<T, const K: usize> Largest<T, K> {
+    pub fn into_inner(self) -> TopK<T, fn(&T, &T) -> Ordering> {
+        self.0
+    }
+}
+
+impl<T, const K: usize> Deref for Largest<T, K> {
+    type Target = TopK<T, fn(&T, &T) -> Ordering>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Ord, const K: usize> FromIterator<T> for Largest<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(TopK::from_iter_with(K, iter))
+    }
+}
+
+impl<T: Debug, const K: usize> Debug for Largest<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
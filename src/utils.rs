@@ -42,6 +42,6 @@ where
     F: Fn(&T, &T) -> Ordering,
 {
     fn partial_cmp(&self, other: &Rev<T, F>) -> Option<Ordering> {
-        Some((self.cmp)(&other.elem, &self.elem))
+        Some(Ord::cmp(self, other))
     }
 }